@@ -0,0 +1,17 @@
+/// The size of the haystacks the benchmarks search, in bytes.
+pub const HAYSTACK_LEN: usize = 1 << 20;
+
+/// About `HAYSTACK_LEN` bytes of log lines, mostly served requests, with the message `rare`
+/// logged every `every` lines if given.
+pub fn haystack(rare: Option<(&str, usize)>) -> String {
+  let mut text = String::with_capacity(HAYSTACK_LEN + 64);
+  let mut i = 0;
+  while text.len() < HAYSTACK_LEN {
+    match rare {
+      Some((message, every)) if i % every == 0 => text.push_str(&format!("2024-01-05 12:00:00 {}\n", message)),
+      _ => text.push_str("2024-01-05 12:00:00 INFO request served in 12ms\n"),
+    }
+    i += 1;
+  }
+  text
+}
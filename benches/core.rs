@@ -0,0 +1,102 @@
+#[macro_use]
+extern crate criterion;
+extern crate verbal_expressions;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use std::borrow::Cow;
+use std::hint::black_box;
+use verbal_expressions::{Alternation, CompiledVerex, Verex};
+
+mod common;
+
+use common::{haystack, HAYSTACK_LEN};
+
+/// A thousand distinct alternatives, none a prefix of another.
+fn alternatives() -> Vec<String> {
+  (0..1000).map(|i| format!("token{:04}x", i)).collect()
+}
+
+fn alternation(words: &[String]) -> Verex {
+  let alternation = words.iter().fold(Alternation::new(), |alternation, word| alternation.literal(word));
+  Verex::new().alternation(&alternation)
+}
+
+fn build(c: &mut Criterion) {
+  let words = alternatives();
+  let verex = alternation(&words);
+
+  let mut group = c.benchmark_group("build");
+  group.bench_function("alternation-1k", |b| b.iter(|| alternation(black_box(&words))));
+  group.bench_function("compile-1k", |b| b.iter(|| black_box(&verex).compile().unwrap()));
+  group.finish();
+}
+
+fn is_match(c: &mut Criterion) {
+  let verex = alternation(&alternatives());
+  let compiled = verex.compile().unwrap();
+  // The same regex, without anything the builder adds, to tell its overhead from the engine's.
+  let wrapped = CompiledVerex::from_regex(verex.try_as_regex().unwrap());
+  let literal = Verex::new().then("token0999x").compile().unwrap();
+  // Regression guard: a pattern of plain literals must take the fast path, or the numbers below
+  // measure the regex engine instead.
+  assert!(literal.is_literal(), "literal pattern lost the fast path");
+  assert!(!wrapped.is_literal());
+
+  let mut group = c.benchmark_group("is-match-1mb");
+  group.throughput(Throughput::Bytes(HAYSTACK_LEN as u64));
+  let miss = haystack(None);
+  let hit_at_end = miss.clone() + "token0999x\n";
+  assert!(!compiled.is_match(&miss) && compiled.is_match(&hit_at_end));
+  for &(name, ref text) in &[("miss", &miss), ("hit-at-end", &hit_at_end)] {
+    group.bench_with_input(BenchmarkId::new("verex", name), text, |b, text| b.iter(|| compiled.is_match(black_box(text))));
+    group.bench_with_input(BenchmarkId::new("regex", name), text, |b, text| b.iter(|| wrapped.is_match(black_box(text))));
+    group.bench_with_input(BenchmarkId::new("literal", name), text, |b, text| b.iter(|| literal.is_match(black_box(text))));
+  }
+  group.finish();
+}
+
+fn find_iter(c: &mut Criterion) {
+  let compiled = Verex::new().then("WARN ").word().compile().unwrap();
+
+  let mut group = c.benchmark_group("find-iter-density");
+  group.throughput(Throughput::Bytes(HAYSTACK_LEN as u64));
+  for &every in &[1, 10, 100, 10_000] {
+    let text = haystack(Some(("WARN token seen", every)));
+    group.bench_with_input(BenchmarkId::from_parameter(format!("1-in-{}", every)), &text, |b, text| {
+      b.iter(|| compiled.find_iter(black_box(text)).count())
+    });
+  }
+  group.finish();
+}
+
+fn replace_all(c: &mut Criterion) {
+  let compiled = Verex::new().then("secret=").word().compile().unwrap();
+  let text = haystack(Some(("WARN secret=hunter2 seen", 10_000)));
+
+  let mut group = c.benchmark_group("replace-all-mostly-clean");
+  group.throughput(Throughput::Bytes(HAYSTACK_LEN as u64));
+  group.bench_function("owned", |b| b.iter(|| compiled.replace_all(black_box(&text), "secret=***")));
+  group.bench_function("cow", |b| b.iter(|| compiled.replace_all_cow(black_box(&text), "secret=***").len()));
+  group.finish();
+}
+
+/// Many short strings, most of which have nothing to replace, as when sanitizing fields one at a
+/// time. `replace_cow` hands the clean ones back borrowed instead of copying them.
+fn replace_clean_strings(c: &mut Criterion) {
+  let compiled = Verex::new().then("secret=").word().compile().unwrap();
+  let texts: Vec<String> = haystack(Some(("WARN secret=hunter2 seen", 1_000))).lines().map(str::to_string).collect();
+  let clean = &texts[1];
+  assert!(matches!(compiled.replace_cow(clean, "secret=***"), Cow::Borrowed(_)), "clean input was copied");
+
+  let mut group = c.benchmark_group("replace-clean-strings");
+  group.throughput(Throughput::Elements(texts.len() as u64));
+  group.bench_function("owned", |b| b.iter(|| texts.iter().map(|text| compiled.replace(black_box(text), "secret=***").len()).sum::<usize>()));
+  group.bench_function("cow", |b| b.iter(|| texts.iter().map(|text| compiled.replace_cow(black_box(text), "secret=***").len()).sum::<usize>()));
+  group.bench_function("is-match-then-replace", |b| b.iter(|| {
+    texts.iter().filter(|text| compiled.is_match(black_box(text))).map(|text| compiled.replace(text, "secret=***").len()).sum::<usize>()
+  }));
+  group.finish();
+}
+
+criterion_group!(benches, build, is_match, find_iter, replace_all, replace_clean_strings);
+criterion_main!(benches);
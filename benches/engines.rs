@@ -0,0 +1,27 @@
+#[macro_use]
+extern crate criterion;
+extern crate verbal_expressions;
+
+use criterion::{Criterion, Throughput};
+use std::hint::black_box;
+use verbal_expressions::Verex;
+
+mod common;
+
+use common::haystack;
+
+fn engines(c: &mut Criterion) {
+  let text = haystack(Some(("ERROR disk quota exceeded on /var", 1000)));
+  let verex = Verex::new().then("ERROR disk quota exceeded on ").then("/var").compile().unwrap();
+  let dfa = verex.to_dense_dfa().unwrap();
+  let lines: Vec<&str> = text.lines().collect();
+
+  let mut group = c.benchmark_group("literal-heavy");
+  group.throughput(Throughput::Bytes(text.len() as u64));
+  group.bench_function("regex", |b| b.iter(|| lines.iter().filter(|line| verex.is_match(black_box(line))).count()));
+  group.bench_function("dense-dfa", |b| b.iter(|| lines.iter().filter(|line| dfa.is_match(black_box(line))).count()));
+  group.finish();
+}
+
+criterion_group!(benches, engines);
+criterion_main!(benches);
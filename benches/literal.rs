@@ -0,0 +1,28 @@
+#[macro_use]
+extern crate criterion;
+extern crate verbal_expressions;
+
+use criterion::{Criterion, Throughput};
+use std::hint::black_box;
+use verbal_expressions::Verex;
+
+mod common;
+
+use common::haystack;
+
+fn literal(c: &mut Criterion) {
+  let text = haystack(Some(("ERROR disk quota exceeded on /var", 1000)));
+  let lines: Vec<&str> = text.lines().collect();
+  let verex = Verex::new().then("ERROR");
+  let compiled = verex.compile().unwrap();
+  let regex = verex.try_as_regex().unwrap();
+
+  let mut group = c.benchmark_group("literal");
+  group.throughput(Throughput::Bytes(text.len() as u64));
+  group.bench_function("fast-path", |b| b.iter(|| lines.iter().filter(|line| compiled.is_match(black_box(line))).count()));
+  group.bench_function("regex", |b| b.iter(|| lines.iter().filter(|line| regex.is_match(black_box(line))).count()));
+  group.finish();
+}
+
+criterion_group!(benches, literal);
+criterion_main!(benches);
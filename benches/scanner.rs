@@ -0,0 +1,41 @@
+#[macro_use]
+extern crate criterion;
+extern crate verbal_expressions;
+
+use criterion::{Criterion, Throughput};
+use std::hint::black_box;
+use verbal_expressions::{Verex, VerexScanner};
+
+mod common;
+
+use common::haystack;
+
+fn patterns() -> Vec<(String, Verex)> {
+  ["ERROR", "WARN", "FATAL", "PANIC", "DENIED", "TIMEOUT", "RETRY", "ABORT"].iter()
+    .map(|level| {
+      let verex = Verex::new().then(level).then(" ").begin_capture().anything().end_capture();
+      (level.to_string(), verex)
+    })
+    .collect()
+}
+
+fn scanner(c: &mut Criterion) {
+  let lines: Vec<String> = haystack(Some(("ERROR job failed", 500))).lines().map(str::to_string).collect();
+  let scanner = VerexScanner::new(patterns()).unwrap();
+  let compiled: Vec<_> = patterns().into_iter().map(|(name, verex)| (name, verex.compile().unwrap())).collect();
+
+  let mut group = c.benchmark_group("multi-pattern");
+  group.throughput(Throughput::Elements(lines.len() as u64));
+  group.bench_function("scanner", |b| b.iter(|| lines.iter().map(|line| scanner.scan(black_box(line)).len()).sum::<usize>()));
+  group.bench_function("each-pattern", |b| {
+    b.iter(|| {
+      lines.iter()
+        .map(|line| compiled.iter().filter(|(_, verex)| !verex.captures(black_box(line)).is_empty()).count())
+        .sum::<usize>()
+    })
+  });
+  group.finish();
+}
+
+criterion_group!(benches, scanner);
+criterion_main!(benches);
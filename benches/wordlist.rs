@@ -0,0 +1,43 @@
+#[macro_use]
+extern crate criterion;
+extern crate verbal_expressions;
+
+use criterion::Criterion;
+use std::hint::black_box;
+use verbal_expressions::{Verex, WordlistOpts};
+
+fn words() -> Vec<String> {
+  let stems = ["foo", "bar", "baz", "qux", "quux", "corge", "grault", "garply", "waldo", "fred"];
+  let suffixes = ["", "s", "ing", "ed", "er", "est", "ly", "ish", "ness", "able"];
+  let mut words = Vec::new();
+  for a in &stems {
+    for b in &stems {
+      for suffix in &suffixes {
+        words.push(format!("{}{}{}", a, b, suffix));
+      }
+    }
+  }
+  words
+}
+
+fn wordlist(c: &mut Criterion) {
+  let words = words();
+  let refs: Vec<&str> = words.iter().map(|word| &word[..]).collect();
+  let naive = WordlistOpts { trie: false, ..WordlistOpts::default() };
+  let text = "the foobarness of quuxfred and waldowaldoable things ".repeat(200);
+
+  let mut group = c.benchmark_group("wordlist-compile");
+  group.bench_function("trie", |b| b.iter(|| Verex::from_wordlist(black_box(&refs)).compile().unwrap()));
+  group.bench_function("naive", |b| b.iter(|| Verex::new().wordlist(black_box(&refs), naive).compile().unwrap()));
+  group.finish();
+
+  let trie = Verex::from_wordlist(&refs).compile().unwrap();
+  let listed = Verex::new().wordlist(&refs, naive).compile().unwrap();
+  let mut group = c.benchmark_group("wordlist-search");
+  group.bench_function("trie", |b| b.iter(|| trie.find_iter(black_box(&text)).count()));
+  group.bench_function("naive", |b| b.iter(|| listed.find_iter(black_box(&text)).count()));
+  group.finish();
+}
+
+criterion_group!(benches, wordlist);
+criterion_main!(benches);
@@ -0,0 +1,151 @@
+use regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use builder::{capture_groups, Step, Verex, VerexFragment, NO_MATCH};
+
+/// Alternatives for `Verex::alternation`, with control over the order they are tried in.
+///
+/// The regex engine takes the first alternative that matches, not the longest, so `in` listed
+/// before `inside` never matches all of "inside". Alternatives are tried in order of descending
+/// `priority`, then in the order they were added. With `longest_match`, literal alternatives of
+/// the same priority are also reordered longest first, so of several literals that match at a
+/// position, the longest wins.
+///
+/// Fragments are never reordered by `longest_match`: whether one fragment can match a longer
+/// text than another is not decidable from their patterns, so a fragment may still win over a
+/// longer literal or fragment listed after it. Use `priority` to order those explicitly.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Alternation {
+  alternatives: Vec<Alternative>,
+  longest_match: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Alternative {
+  kind: AlternativeKind,
+  priority: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum AlternativeKind {
+  Literal(String),
+  Fragment(Vec<Step>),
+}
+
+impl Alternation {
+  pub fn new() -> Alternation {
+    Alternation::default()
+  }
+
+  /// Adds an alternative matching `value` literally.
+  pub fn literal(mut self, value: &str) -> Alternation {
+    self.alternatives.push(Alternative { kind: AlternativeKind::Literal(value.to_string()), priority: 0 });
+    self
+  }
+
+  /// Adds an alternative matching `fragment`.
+  pub fn fragment(mut self, fragment: VerexFragment) -> Alternation {
+    self.alternatives.push(Alternative { kind: AlternativeKind::Fragment(fragment.steps().to_vec()), priority: 0 });
+    self
+  }
+
+  /// Sets the priority of the alternative added last. Alternatives default to priority 0.
+  ///
+  /// # Panics
+  ///
+  /// Panics if no alternative has been added yet.
+  pub fn priority(mut self, priority: i32) -> Alternation {
+    self.alternatives.last_mut().expect("priority() needs an alternative to apply to").priority = priority;
+    self
+  }
+
+  pub fn longest_match(mut self, enabled: bool) -> Alternation {
+    self.longest_match = enabled;
+    self
+  }
+
+  pub(crate) fn fragments(&self) -> Vec<&[Step]> {
+    self.alternatives.iter().filter_map(|alternative| match alternative.kind {
+      AlternativeKind::Fragment(ref steps) => Some(&steps[..]),
+      AlternativeKind::Literal(_) => None,
+    }).collect()
+  }
+
+  /// The alternatives in the order they are tried, with their index in the order they were
+  /// added.
+  fn ordered(&self) -> Vec<(usize, &Alternative)> {
+    let mut ordered: Vec<(usize, &Alternative)> = self.alternatives.iter().enumerate().collect();
+    ordered.sort_by_key(|&(_, alternative)| -i64::from(alternative.priority));
+    if !self.longest_match {
+      return ordered;
+    }
+
+    // Sort the literals of each priority among the positions they already hold, leaving the
+    // fragments where they are.
+    let mut start = 0;
+    while start < ordered.len() {
+      let priority = ordered[start].1.priority;
+      let end = ordered[start..].iter()
+        .position(|&(_, alternative)| alternative.priority != priority)
+        .map_or(ordered.len(), |n| start + n);
+      let slots: Vec<usize> = (start..end).filter(|&i| ordered[i].1.is_literal()).collect();
+      let mut literals: Vec<(usize, &Alternative)> = slots.iter().map(|&i| ordered[i]).collect();
+      literals.sort_by_key(|&(_, alternative)| match alternative.kind {
+        AlternativeKind::Literal(ref value) => -(value.chars().count() as i64),
+        AlternativeKind::Fragment(_) => 0,
+      });
+      for (slot, literal) in slots.into_iter().zip(literals) {
+        ordered[slot] = literal;
+      }
+      start = end;
+    }
+    ordered
+  }
+}
+
+impl Alternative {
+  fn is_literal(&self) -> bool {
+    match self.kind {
+      AlternativeKind::Literal(_) => true,
+      AlternativeKind::Fragment(_) => false,
+    }
+  }
+
+  fn pattern(&self) -> String {
+    match self.kind {
+      AlternativeKind::Literal(ref value) => regex::escape(value),
+      AlternativeKind::Fragment(ref steps) => format!("(?:{})", Verex::from_steps(steps).as_string()),
+    }
+  }
+}
+
+impl Verex {
+  /// Matches any of the alternatives of `alternation`, in the order it determines. With no
+  /// alternatives, nothing matches. The capture groups of fragments are numbered in the order
+  /// they are tried; `composition_map` tells where they went.
+  pub fn alternation(self, alternation: &Alternation) -> Verex {
+    let ordered = alternation.ordered();
+    let alternatives: Vec<String> = ordered.iter().map(|&(_, alternative)| alternative.pattern()).collect();
+    let pattern = if alternatives.is_empty() { NO_MATCH.to_string() } else { alternatives.join("|") };
+
+    // The groups before each fragment's first, within the alternation, by the order they were added.
+    let mut fragments = vec![None; alternation.alternatives.len()];
+    let mut before = 0;
+    for (&(i, alternative), pattern) in ordered.iter().zip(&alternatives) {
+      let groups = capture_groups(pattern).len();
+      if !alternative.is_literal() {
+        fragments[i] = Some((before, groups));
+      }
+      before += groups;
+    }
+    let fragments: Vec<(usize, usize)> = fragments.into_iter().flatten().collect();
+    self.record(Step::Alternation(alternation.clone()), |mut v| {
+      v.renumber_captures(&fragments);
+      v.atom(&format!("(?:{})", pattern))
+    })
+  }
+}
@@ -0,0 +1,334 @@
+use proptest::prelude::*;
+use proptest::strategy::{NewTree, ValueTree};
+use proptest::test_runner::TestRunner;
+
+use builder::Verex;
+
+const MAX_DEPTH: u32 = 3;
+const MAX_OPS: usize = 12;
+
+#[derive(Clone, Debug)]
+enum Op {
+  StartOfLine,
+  EndOfLine,
+  Then(String),
+  Maybe(String),
+  Anything,
+  AnythingBut(String),
+  Something,
+  SomethingBut(String),
+  LineBreak,
+  Tab,
+  Word,
+  NonWord,
+  Digit,
+  NonDigit,
+  Space,
+  NonSpace,
+  OptionalWhitespace,
+  RequiredWhitespace,
+  AnyOf(String),
+  Range(Vec<(char, char)>),
+  WithAnyCase,
+  WithSpecificCase,
+  SearchMultiline,
+  SearchOneline,
+  Quantified(Box<Op>, Quantifier),
+  Capture(Vec<Op>),
+  Padded(Vec<Op>),
+  Or(String),
+}
+
+#[derive(Clone, Debug)]
+enum Quantifier {
+  OneOrMore,
+  ZeroOrMore,
+  Count(i32),
+  CountRange(i32, i32),
+  AtLeast(i32),
+}
+
+impl Op {
+  fn apply(&self, v: Verex) -> Verex {
+    match *self {
+      Op::StartOfLine => v.start_of_line(),
+      Op::EndOfLine => v.end_of_line(),
+      Op::Then(ref value) => v.then(value),
+      Op::Maybe(ref value) => v.maybe(value),
+      Op::Anything => v.anything(),
+      Op::AnythingBut(ref value) => v.anything_but(value),
+      Op::Something => v.something(),
+      Op::SomethingBut(ref value) => v.something_but(value),
+      Op::LineBreak => v.line_break(),
+      Op::Tab => v.tab(),
+      Op::Word => v.word(),
+      Op::NonWord => v.non_word(),
+      Op::Digit => v.digit(),
+      Op::NonDigit => v.non_digit(),
+      Op::Space => v.space(),
+      Op::NonSpace => v.non_space(),
+      Op::OptionalWhitespace => v.optional_whitespace(),
+      Op::RequiredWhitespace => v.required_whitespace(),
+      Op::AnyOf(ref value) => v.any_of(value),
+      Op::Range(ref pairs) => v.range(pairs),
+      Op::WithAnyCase => v.with_any_case(),
+      Op::WithSpecificCase => v.with_specific_case(),
+      Op::SearchMultiline => v.search_multiline(),
+      Op::SearchOneline => v.search_oneline(),
+      Op::Quantified(ref op, ref quantifier) => {
+        let v = op.apply(v);
+        match *quantifier {
+          Quantifier::OneOrMore => v.one_or_more(),
+          Quantifier::ZeroOrMore => v.zero_or_more(),
+          Quantifier::Count(n) => v.count(n),
+          Quantifier::CountRange(from, to) => v.count_range(from, to),
+          Quantifier::AtLeast(n) => v.at_least(n),
+        }
+      },
+      Op::Capture(ref ops) => apply_all(ops, v.begin_capture()).end_capture(),
+      Op::Padded(ref ops) => v.padded(|inner| apply_all(ops, inner)),
+      Op::Or(ref value) => v.or(value),
+    }
+  }
+}
+
+fn apply_all(ops: &[Op], v: Verex) -> Verex {
+  ops.iter().fold(v, |v, op| op.apply(v))
+}
+
+fn literal() -> BoxedStrategy<String> {
+  "[a-z0-9]{1,4}".boxed()
+}
+
+fn quantifier() -> BoxedStrategy<Quantifier> {
+  prop_oneof![
+    Just(Quantifier::OneOrMore),
+    Just(Quantifier::ZeroOrMore),
+    (0..5i32).prop_map(Quantifier::Count),
+    (0..5i32, 0..5i32).prop_map(|(a, b)| Quantifier::CountRange(a, a + b)),
+    (0..5i32).prop_map(Quantifier::AtLeast),
+  ].boxed()
+}
+
+fn range_pair() -> BoxedStrategy<(char, char)> {
+  (0..26u8, 0..26u8).prop_map(|(a, b)| {
+    let (from, to) = if a <= b { (a, b) } else { (b, a) };
+    ((b'a' + from) as char, (b'a' + to) as char)
+  }).boxed()
+}
+
+fn atom() -> BoxedStrategy<Op> {
+  prop_oneof![
+    17 => quantifiable(),
+    1 => literal().prop_map(Op::Maybe),
+  ].boxed()
+}
+
+/// The atoms that end without a quantifier of their own, which another one would stack on.
+fn quantifiable() -> BoxedStrategy<Op> {
+  prop_oneof![
+    literal().prop_map(Op::Then),
+    Just(Op::Anything),
+    literal().prop_map(Op::AnythingBut),
+    Just(Op::Something),
+    literal().prop_map(Op::SomethingBut),
+    Just(Op::LineBreak),
+    Just(Op::Tab),
+    Just(Op::Word),
+    Just(Op::NonWord),
+    Just(Op::Digit),
+    Just(Op::NonDigit),
+    Just(Op::Space),
+    Just(Op::NonSpace),
+    Just(Op::OptionalWhitespace),
+    Just(Op::RequiredWhitespace),
+    literal().prop_map(Op::AnyOf),
+    prop::collection::vec(range_pair(), 1..3).prop_map(Op::Range),
+  ].boxed()
+}
+
+fn flat_op() -> BoxedStrategy<Op> {
+  prop_oneof![
+    4 => atom(),
+    2 => (quantifiable(), quantifier()).prop_map(|(op, q)| Op::Quantified(Box::new(op), q)),
+    1 => Just(Op::StartOfLine),
+    1 => Just(Op::EndOfLine),
+    1 => prop_oneof![
+      Just(Op::WithAnyCase),
+      Just(Op::WithSpecificCase),
+      Just(Op::SearchMultiline),
+      Just(Op::SearchOneline),
+    ],
+  ].boxed()
+}
+
+fn nested_op() -> BoxedStrategy<Op> {
+  flat_op().prop_recursive(MAX_DEPTH, 32, 4, |inner| {
+    prop_oneof![
+      (atom(), prop::collection::vec(inner.clone(), 0..4)).prop_map(|(first, mut ops)| {
+        ops.insert(0, first);
+        Op::Capture(ops)
+      }),
+      prop::collection::vec(inner, 0..4).prop_map(Op::Padded),
+    ]
+  }).boxed()
+}
+
+fn top_level_op() -> BoxedStrategy<Op> {
+  prop_oneof![
+    8 => nested_op(),
+    1 => literal().prop_map(Op::Or),
+  ].boxed()
+}
+
+/// Generates random but valid `Verex` values out of sequences of combinators.
+#[derive(Debug)]
+pub struct VerexStrategy {
+  ops: BoxedStrategy<Vec<Op>>,
+}
+
+/// Shrinks a generated `Verex` by removing its trailing combinators.
+#[derive(Debug)]
+pub struct VerexValueTree {
+  ops: Vec<Op>,
+  len: usize,
+  prev_len: Option<usize>,
+}
+
+impl Strategy for VerexStrategy {
+  type Tree = VerexValueTree;
+  type Value = Verex;
+
+  fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+    let ops = self.ops.new_tree(runner)?.current();
+    Ok(VerexValueTree { len: ops.len(), ops, prev_len: None })
+  }
+}
+
+impl ValueTree for VerexValueTree {
+  type Value = Verex;
+
+  fn current(&self) -> Verex {
+    apply_all(&self.ops[..self.len], Verex::new())
+  }
+
+  fn simplify(&mut self) -> bool {
+    if self.len == 0 {
+      return false;
+    }
+    self.prev_len = Some(self.len);
+    self.len -= 1;
+    true
+  }
+
+  fn complicate(&mut self) -> bool {
+    match self.prev_len.take() {
+      Some(len) => {
+        self.len = len;
+        true
+      },
+      None => false,
+    }
+  }
+}
+
+impl Arbitrary for Verex {
+  type Parameters = ();
+  type Strategy = VerexStrategy;
+
+  fn arbitrary_with(_: ()) -> VerexStrategy {
+    let ops = (atom(), prop::collection::vec(top_level_op(), 0..MAX_OPS)).prop_map(|(first, mut ops)| {
+      ops.insert(0, first);
+      ops
+    });
+    VerexStrategy { ops: ops.boxed() }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use proptest::prelude::*;
+  use proptest::strategy::ValueTree;
+  use proptest::test_runner::TestRunner;
+
+  use builder::{Step, Verex};
+  use escape::{escape, escape_class};
+  use super::apply_all;
+
+  const METACHARACTERS: &str = r"\.+*?()|[]{}^$#&-~";
+
+  proptest! {
+    #[test]
+    fn test_arbitrary_compiles(v in any::<Verex>()) {
+      let pattern = v.clone().as_string();
+      prop_assert!(v.try_as_regex().is_ok(), "failed to compile {}", pattern);
+    }
+
+    #[test]
+    fn test_parse_round_trips(v in any::<Verex>(), haystack in "[a-zA-Z0-9 \t\n.,_-]{0,16}") {
+      let pattern = v.clone().as_string();
+      let parsed = Verex::parse(&pattern);
+      prop_assert!(parsed.is_ok(), "failed to parse {}", pattern);
+      let parsed = parsed.unwrap();
+      prop_assert!(!has_opaque(parsed.steps()), "{} parsed as {:?}", pattern, parsed.steps());
+      prop_assert_eq!(parsed.clone().as_string(), pattern.clone(), "{:?}", parsed.steps());
+      let (original, parsed) = (v.try_as_regex().unwrap(), parsed.try_as_regex().unwrap());
+      for example in v.examples(8).unwrap_or_default().iter().chain(Some(&haystack)) {
+        prop_assert_eq!(original.find(example).map(|m| m.range()), parsed.find(example).map(|m| m.range()), "{} on {:?}", pattern, example);
+      }
+    }
+
+    #[test]
+    fn test_pretty_string_compiles_alike(v in any::<Verex>(), haystack in "[a-zA-Z0-9 \t\n.,_#-]{0,16}") {
+      let pretty = v.to_pretty_string();
+      let compiled = regex::RegexBuilder::new(&pretty).ignore_whitespace(true).build();
+      prop_assert!(compiled.is_ok(), "failed to compile {}", pretty);
+      let original = v.try_as_regex().unwrap();
+      prop_assert_eq!(compiled.unwrap().find(&haystack).map(|m| m.range()), original.find(&haystack).map(|m| m.range()), "{}", pretty);
+    }
+
+    #[test]
+    fn test_escape_matches_literally(s in r"[a-z\\.+*?()|\[\]{}^$#&~-]{0,12}") {
+      let exact = Verex::new().start_of_line().raw(&escape(&s)).end_of_line();
+      prop_assert!(exact.clone().is_match(&s));
+      if let Some(i) = s.find(|c| METACHARACTERS.contains(c)) {
+        let mut expanded = s.clone();
+        expanded.replace_range(i..i + 1, "a");
+        prop_assert!(!exact.is_match(&expanded));
+      }
+    }
+
+    #[test]
+    fn test_escape_class_matches_exactly(s in r"[a-z\\.+*?()|\[\]{}^$#&~-]{1,12}") {
+      let class = Verex::new().start_of_line().raw(&format!("[{}]+", escape_class(&s))).end_of_line();
+      prop_assert!(class.clone().is_match(&s));
+      prop_assert!(!class.is_match("Z"));
+    }
+  }
+
+  /// Whether `steps` keep any part of a parsed pattern as an opaque fragment.
+  fn has_opaque(steps: &[Step]) -> bool {
+    steps.iter().any(|step| match *step {
+      Step::FromRegex(_) => true,
+      Step::Capture(ref steps) | Step::Concat(ref steps) => has_opaque(steps),
+      Step::Alternation(ref alternation) => alternation.fragments().into_iter().any(has_opaque),
+      _ => false,
+    })
+  }
+
+  #[test]
+  fn test_arbitrary_shrinks_trailing_combinators() {
+    let mut runner = TestRunner::deterministic();
+    let mut tree = any::<Verex>().new_tree(&mut runner).unwrap();
+    while tree.ops.len() < 2 {
+      tree = any::<Verex>().new_tree(&mut runner).unwrap();
+    }
+    let n = tree.ops.len();
+    assert!(tree.simplify());
+    assert_eq!(tree.current().as_string(), apply_all(&tree.ops[..n - 1], Verex::new()).as_string());
+    assert!(tree.complicate());
+    assert_eq!(tree.current().as_string(), apply_all(&tree.ops, Verex::new()).as_string());
+    while tree.simplify() {}
+    assert_eq!(tree.current().as_string(), "");
+  }
+}
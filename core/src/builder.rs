@@ -0,0 +1,2536 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use regex::Regex;
+use regex_syntax::ast::parse::Parser;
+use regex_syntax::ast::{self, Ast, GroupKind};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use alternation::Alternation;
+use classes::single_class;
+use compiled::{CompiledVerex, SharedVerex};
+use error::{validate_steps, VerexError};
+#[cfg(feature = "tracing")]
+use instrument;
+#[cfg(feature = "unicode-normalization")]
+use normalize;
+use parse;
+use presets::{CoordOpts, DateFormat, EmailStrictness, HostnameOpts, IdentLang, IndentUnit, NumberFormat, PasswordPolicy, PlateRegion, RomanCase, UrlOpts, UuidOpts, WordlistOpts, QUOTED_STRING};
+use strict::VerexBuilder;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+  Literal(String),
+  Atom(String),
+  Quantifier(String),
+  Group(Vec<Token>),
+  Raw(String),
+  Tagged(String),
+  Fancy { pattern: String, fallback: Option<String> },
+  /// A pattern that can match bytes that are not valid UTF-8, for `try_as_bytes_regex` only.
+  Bytes(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(feature = "fancy"), allow(dead_code))]
+enum Backend {
+  Default,
+  Fancy,
+}
+
+/// 64-bit FNV-1a, used for fingerprints because its output, unlike that of `DefaultHasher`, is
+/// the same on every platform and Rust release.
+struct Fnv(u64);
+
+impl Fnv {
+  fn new() -> Fnv {
+    Fnv(0xcbf2_9ce4_8422_2325)
+  }
+
+  fn byte(&mut self, byte: u8) {
+    self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+  }
+
+  fn str(&mut self, value: &str) {
+    for &byte in (value.len() as u64).to_le_bytes().iter().chain(value.as_bytes()) {
+      self.byte(byte);
+    }
+  }
+
+  fn tokens(&mut self, tokens: &[Token]) {
+    for token in tokens {
+      match *token {
+        Token::Literal(ref value) => { self.byte(0); self.str(value) },
+        Token::Atom(ref value) => { self.byte(1); self.str(value) },
+        Token::Quantifier(ref value) => { self.byte(2); self.str(value) },
+        Token::Group(ref inner) => {
+          self.byte(3);
+          self.tokens(inner);
+          self.byte(4);
+        },
+        Token::Raw(ref value) => { self.byte(5); self.str(value) },
+        Token::Tagged(ref tag) => { self.byte(6); self.str(tag) },
+        Token::Fancy { ref pattern, ref fallback } => {
+          self.byte(7);
+          self.str(pattern);
+          match *fallback {
+            Some(ref fallback) => { self.byte(1); self.str(fallback) },
+            None => self.byte(0),
+          }
+        },
+        Token::Bytes(ref pattern) => { self.byte(8); self.str(pattern) },
+      }
+    }
+  }
+}
+
+fn anchored_match(flags: &str, pattern: &str, text: &str) -> Option<usize> {
+  Regex::new(&format!("\\A(?:{}{})", flags, pattern)).ok()
+    .and_then(|regex| regex.find(text))
+    .map(|m| m.end())
+}
+
+fn render(tokens: &[Token]) -> String {
+  render_for(tokens, Backend::Default)
+}
+
+fn render_for(tokens: &[Token], backend: Backend) -> String {
+  tokens.iter().fold(String::new(), |buf, token| match *token {
+    Token::Literal(ref s) => buf + "(?:" + s + ")",
+    Token::Atom(ref s) | Token::Quantifier(ref s) | Token::Raw(ref s) | Token::Bytes(ref s) => buf + s,
+    Token::Group(ref tokens) => buf + "(?:" + &render_for(tokens, backend) + ")",
+    Token::Tagged(_) => buf + "(",
+    Token::Fancy { ref fallback, .. } if backend == Backend::Default && fallback.is_some() => {
+      buf + fallback.as_ref().unwrap()
+    },
+    Token::Fancy { ref pattern, .. } => buf + pattern,
+  })
+}
+
+/// `pattern` with the whitespace and `#` that are not escaped escaped, so that it means the same
+/// with the `x` flag.
+fn free_spacing(pattern: &str) -> String {
+  let mut escaped = String::with_capacity(pattern.len());
+  let mut chars = pattern.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => {
+        escaped.push(c);
+        escaped.extend(chars.next());
+      },
+      '#' => escaped.push_str(r"\#"),
+      c if c.is_whitespace() => escaped.push_str(&format!(r"\x{{{:X}}}", u32::from(c))),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// The groups that `pattern` opens, less those it closes. Escaped characters and character
+/// classes are skipped.
+fn paren_depth(pattern: &str) -> isize {
+  let bytes = pattern.as_bytes();
+  let mut depth = 0;
+  let mut class_depth = 0;
+  let mut i = 0;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'[' => {
+        class_depth += 1;
+        if bytes.get(i + 1) == Some(&b'^') {
+          i += 1;
+        }
+        if bytes.get(i + 1) == Some(&b']') {
+          i += 1;
+        }
+      },
+      b']' if class_depth > 0 => class_depth -= 1,
+      b'(' if class_depth == 0 => depth += 1,
+      b')' if class_depth == 0 => depth -= 1,
+      _ => {},
+    }
+    i += 1;
+  }
+  depth
+}
+
+/// The step that left each token as it is, given the index of that step in `steps` for each.
+fn token_labels<'a>(steps: &'a [Step], origins: &[usize]) -> Vec<Option<&'a Step>> {
+  origins.iter().map(|&i| steps.get(i)).collect()
+}
+
+/// The steps of the fragment that a step adds as a group.
+fn nested_steps(step: &Step) -> Option<&[Step]> {
+  match *step {
+    Step::Capture(ref steps) | Step::Padded(ref steps) | Step::Concat(ref steps) |
+    Step::Atomic(ref steps) | Step::RepeatFragment(ref steps, _) => Some(steps),
+    _ => None,
+  }
+}
+
+/// The steps that added each of `tokens`, the tokens of a group, if `steps` built them, possibly
+/// between tokens of the step that nests them.
+fn nested_labels<'a>(tokens: &[Token], steps: Option<&'a [Step]>) -> Vec<Option<&'a Step>> {
+  let mut labels = vec![None; tokens.len()];
+  if let Some(steps) = steps {
+    let built = Verex::from_steps(steps);
+    let start = (0..=tokens.len().saturating_sub(built.tokens.len())).find(|&i| tokens[i..].starts_with(&built.tokens));
+    if let Some(start) = start {
+      labels.splice(start..start + built.tokens.len(), token_labels(steps, &built.origins));
+    }
+  }
+  labels
+}
+
+/// The lookahead that `until(value)` adds for a `value` longer than a character.
+fn until_lookahead(value: &str) -> String {
+  format!("(?:.*?(?={}))", regex::escape(value))
+}
+
+/// Gives the lookahead `pattern` of `until` that `tokens` end with, possibly before the
+/// parentheses of groups that close after it, the lazy fallback that the delimiter following
+/// it makes equivalent.
+fn delimit_until(tokens: &mut [Token], pattern: &str) {
+  let closing = tokens.iter().rev().take_while(|&token| matches!(*token, Token::Raw(ref s) if s == ")")).count();
+  let end = tokens.len() - closing;
+  match tokens[..end].last_mut() {
+    Some(&mut Token::Fancy { pattern: ref lookahead, ref mut fallback }) if lookahead == pattern => {
+      *fallback = Some("(?:.*?)".to_string());
+    },
+    Some(&mut Token::Group(ref mut inner)) => delimit_until(inner, pattern),
+    _ => {},
+  }
+}
+
+/// Writes a pattern one unit per line for `Verex::to_pretty_string`, indented by group.
+struct Pretty {
+  out: String,
+  depth: usize,
+}
+
+impl Pretty {
+  /// Writes `pattern` on a line of its own, followed by a comment naming `labels`. A line that
+  /// starts by closing groups is indented as the groups it closes.
+  fn line(&mut self, pattern: &str, labels: &[Option<&Step>]) {
+    let closing = pattern.bytes().take_while(|&b| b == b')').count();
+    let indent = self.depth.saturating_sub(closing);
+    self.out.push_str(&"  ".repeat(indent));
+    self.out.push_str(&free_spacing(pattern));
+    let mut names: Vec<&str> = Vec::new();
+    for name in labels.iter().flatten().map(|step| step.name()) {
+      if !names.contains(&name) {
+        names.push(name);
+      }
+    }
+    if !names.is_empty() {
+      self.out.push_str("  # ");
+      self.out.push_str(&names.join(", "));
+    }
+    self.out.push('\n');
+    self.depth = (self.depth as isize + paren_depth(pattern)).max(0) as usize;
+  }
+
+  /// Writes `tokens`, labelled by `labels`, one for each of them.
+  fn tokens(&mut self, tokens: &[Token], labels: &[Option<&Step>]) {
+
+    let mut i = 0;
+    while i < tokens.len() {
+      let mut end = i + 1;
+      while let Some(&Token::Quantifier(_)) = tokens.get(end) {
+        end += 1;
+      }
+      match tokens[i] {
+        Token::Group(ref inner) => {
+          self.line("(?:", &labels[i..i + 1]);
+          self.tokens(inner, &nested_labels(inner, labels[i].and_then(nested_steps)));
+          self.line(&(")".to_string() + &render(&tokens[i + 1..end])), &labels[i + 1..end]);
+        },
+        _ => self.line(&render(&tokens[i..end]), &labels[i..end]),
+      }
+      i = end;
+    }
+  }
+}
+
+const TAG_MARKER: &str = "__verex_tag";
+const LENGTH_MARKER: &str = "__verex_length";
+
+/// A pattern that matches nothing, not even the empty string.
+pub(crate) const NO_MATCH: &str = r"[^\s\S]";
+
+/// The first tag that `tokens` give to more than one group.
+fn duplicate_tag(tokens: &[Token]) -> Option<String> {
+  fn tags<'a>(tokens: &'a [Token], seen: &mut HashSet<&'a str>) -> Option<String> {
+    tokens.iter().find_map(|token| match *token {
+      Token::Tagged(ref tag) if !seen.insert(tag) => Some(tag.clone()),
+      Token::Group(ref tokens) => tags(tokens, seen),
+      _ => None,
+    })
+  }
+  tags(tokens, &mut HashSet::new())
+}
+
+/// Turns the first group tagged `tag` into a group named `TAG_MARKER`, so that its index can be
+/// looked up in the compiled pattern.
+fn mark_tag(tokens: &mut [Token], tag: &str) -> bool {
+  for token in tokens {
+    let found = match *token {
+      Token::Tagged(ref t) => t == tag,
+      Token::Group(ref mut tokens) => {
+        if mark_tag(tokens, tag) {
+          return true;
+        }
+        false
+      },
+      _ => false,
+    };
+    if found {
+      *token = Token::Raw(format!("(?P<{}>", TAG_MARKER));
+      return true;
+    }
+  }
+  false
+}
+
+/// The named groups of `pattern` in order, with the byte range of each name.
+fn group_names(pattern: &str) -> Vec<(Range<usize>, &str)> {
+  capture_groups(pattern).into_iter().flatten().collect()
+}
+
+/// The capture groups of `pattern` in order, with the byte range of the name of each named
+/// one, read from its `regex-syntax` AST. An invalid pattern has none.
+pub(crate) fn capture_groups(pattern: &str) -> Vec<Option<(Range<usize>, &str)>> {
+  // `regex-syntax` rejects names used twice and what only the `fancy` backend knows, so those
+  // are rewritten until the pattern parses. Each rewrite is kept as its start in `parsed`, its
+  // length there and its length in `pattern`.
+  let mut parsed = pattern.to_string();
+  let mut rewrites: Vec<(usize, usize, usize)> = Vec::new();
+  let ast = loop {
+    let err = match Parser::new().parse(&parsed) {
+      Ok(ast) => break ast,
+      Err(err) => err,
+    };
+    let (mut start, mut end) = (err.span().start.offset, err.span().end.offset);
+    let replacement = match *err.kind() {
+      ast::ErrorKind::GroupNameDuplicate { .. } => {
+        (0..).map(|n| format!("_{}", n)).find(|name| !parsed.contains(&format!("<{}>", name))).unwrap()
+      },
+      ast::ErrorKind::UnsupportedLookAround => "(?:".to_string(),
+      ast::ErrorKind::UnsupportedBackreference => "(?:)".to_string(),
+      ast::ErrorKind::EscapeUnrecognized if parsed[start..].starts_with(r"\k<") => match parsed[start..].find('>') {
+        Some(len) => {
+          end = start + len + 1;
+          "(?:)".to_string()
+        },
+        None => return Vec::new(),
+      },
+      ast::ErrorKind::FlagUnrecognized if parsed[..end].ends_with("(?>") => {
+        start -= 2;
+        "(?:".to_string()
+      },
+      _ => return Vec::new(),
+    };
+    // The parser reports the first error it meets, so each rewrite comes after the last.
+    if rewrites.last().is_some_and(|&(last, len, _)| start < last + len) {
+      return Vec::new();
+    }
+    parsed.replace_range(start..end, &replacement);
+    rewrites.push((start, replacement.len(), end - start));
+  };
+
+  let mut names = Vec::new();
+  named_groups(&ast, &mut names);
+  names.into_iter().map(|span| span.map(|span| {
+    let (mut start, mut len) = (span.start.offset, span.end.offset - span.start.offset);
+    let offset = span.start.offset;
+    for &(at, new_len, old_len) in &rewrites {
+      if at < offset {
+        start = start + old_len - new_len;
+      } else if at == offset {
+        len = old_len;
+      }
+    }
+    (start..start + len, &pattern[start..start + len])
+  })).collect()
+}
+
+/// Pushes the span of the name of each capture group in `ast` onto `names` in order, or `None`
+/// for an unnamed one.
+fn named_groups(ast: &Ast, names: &mut Vec<Option<ast::Span>>) {
+  match *ast {
+    Ast::Group(ref group) => {
+      match group.kind {
+        GroupKind::CaptureIndex(_) => names.push(None),
+        GroupKind::CaptureName { ref name, .. } => names.push(Some(name.span)),
+        GroupKind::NonCapturing(_) => {},
+      }
+      named_groups(&group.ast, names);
+    },
+    Ast::Repetition(ref repetition) => named_groups(&repetition.ast, names),
+    Ast::Alternation(ref alternation) => alternation.asts.iter().for_each(|ast| named_groups(ast, names)),
+    Ast::Concat(ref concat) => concat.asts.iter().for_each(|ast| named_groups(ast, names)),
+    _ => {},
+  }
+}
+
+/// The index of the token that begins the innermost capture group `tokens` leave open.
+fn innermost_open_group(tokens: &[Token]) -> Option<usize> {
+  let mut depth = 0;
+  for (i, token) in tokens.iter().enumerate().rev() {
+    let opens = match *token {
+      Token::Raw(ref raw) if raw == ")" => {
+        depth += 1;
+        continue;
+      },
+      Token::Raw(ref raw) => raw == "(" || raw.starts_with("(?P<"),
+      Token::Tagged(_) => true,
+      _ => false,
+    };
+    if opens {
+      if depth == 0 {
+        return Some(i);
+      }
+      depth -= 1;
+    }
+  }
+  None
+}
+
+/// The first group name that `pattern` uses more than once.
+fn duplicate_group_name(pattern: &str) -> Option<String> {
+  let mut seen = HashSet::new();
+  group_names(pattern).into_iter().find(|&(_, name)| !seen.insert(name)).map(|(_, name)| name.to_string())
+}
+
+/// Renames every group after the first of its name to `name_2`, `name_3` and so on, skipping
+/// names that `pattern` already uses.
+fn suffix_duplicate_groups(pattern: &str) -> String {
+  let names = group_names(pattern);
+  let mut taken: HashSet<String> = names.iter().map(|&(_, name)| name.to_string()).collect();
+  let mut seen = HashSet::new();
+  let mut resolved = String::with_capacity(pattern.len());
+  let mut copied = 0;
+  for (range, name) in names {
+    if seen.insert(name) {
+      continue;
+    }
+    let renamed = (2..).map(|n| format!("{}_{}", name, n)).find(|candidate| !taken.contains(candidate)).expect("some suffix is free");
+    resolved.push_str(&pattern[copied..range.start]);
+    resolved.push_str(&renamed);
+    copied = range.end;
+    taken.insert(renamed);
+  }
+  resolved.push_str(&pattern[copied..]);
+  resolved
+}
+
+fn requires_fancy(tokens: &[Token]) -> bool {
+  tokens.iter().any(|token| match *token {
+    Token::Group(ref tokens) => requires_fancy(tokens),
+    Token::Fancy { ref fallback, .. } => fallback.is_none(),
+    _ => false,
+  })
+}
+
+/// Whether any of `tokens` is a lookaround, even one with a fallback for the default backend.
+fn has_fancy(tokens: &[Token]) -> bool {
+  tokens.iter().any(|token| match *token {
+    Token::Group(ref tokens) => has_fancy(tokens),
+    Token::Fancy { .. } => true,
+    _ => false,
+  })
+}
+
+fn requires_bytes(tokens: &[Token]) -> bool {
+  tokens.iter().any(|token| match *token {
+    Token::Group(ref tokens) => requires_bytes(tokens),
+    Token::Bytes(_) => true,
+    _ => false,
+  })
+}
+
+/// The first token that only the `fancy` or bytes backend accepts.
+fn backend_token(tokens: &[Token]) -> Option<&str> {
+  tokens.iter().filter_map(|token| match *token {
+    Token::Group(ref tokens) => backend_token(tokens),
+    Token::Fancy { ref pattern, fallback: None } | Token::Bytes(ref pattern) => Some(&pattern[..]),
+    _ => None,
+  }).next()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Step {
+  StartOfLine,
+  EndOfLine,
+  Pop,
+  FromRegex(String),
+  Find(String),
+  Raw(String),
+  Then(String),
+  Maybe(String),
+  ThenAnyCase(String),
+  ThenExactCase(String),
+  ThenCasefold(String),
+  StartsWith(String),
+  EndsWith(String),
+  Anything,
+  AnythingButNot(String),
+  AnythingBut(String),
+  Something,
+  SomethingButNot(String),
+  SomethingBut(String),
+  Until(String),
+  UntilAndIncluding(String),
+  NotStartingWith(String),
+  NotEndingWith(String),
+  LineBreak,
+  Br,
+  Tab,
+  Word,
+  NonWord,
+  Digit,
+  NonDigit,
+  UnicodeDigit,
+  AsciiDigit,
+  Space,
+  NonSpace,
+  OptionalWhitespace,
+  RequiredWhitespace,
+  OptionalHorizontalWhitespace,
+  RequiredHorizontalWhitespace,
+  Padded(Vec<Step>),
+  AnyOf(String),
+  Any(String),
+  Range(Vec<(String, String)>),
+  AddModifier(String),
+  RemoveModifier(String),
+  SetFlag(Flag),
+  ClearFlag(Flag),
+  WithAnyCase,
+  WithSpecificCase,
+  SearchMultiline,
+  SearchOneline,
+  Multiple(String, Vec<i32>),
+  Repeated(String, Rep),
+  OneOrMore,
+  ZeroOrMore,
+  Count(i32),
+  CountRange(i32, i32),
+  CountRangeLazy(i32, i32),
+  AtLeast(i32),
+  AtLeastLazy(i32),
+  Lazy,
+  Possessive,
+  Or(String),
+  BeginCapture,
+  BeginNamedCapture(String),
+  BeginCaptureTagged(String),
+  EndCapture,
+  Email(EmailStrictness),
+  Scheme,
+  Userinfo,
+  Host,
+  Port,
+  Path,
+  Query,
+  Fragment,
+  Url(UrlOpts),
+  IsoWeekDate,
+  Hashtag,
+  Mention,
+  WordLike(char),
+  DigitGrouped(usize, char),
+  DigitGroupedOrPlain(usize, char),
+  Uuid(UuidOpts),
+  LineComment(String),
+  LineCommentWithNewline(String),
+  BlockComment(String, String),
+  Isbn10,
+  Isbn13,
+  Isbn,
+  Ean13,
+  Coordinates(CoordOpts),
+  PasswordPolicy(PasswordPolicy),
+  Percent,
+  Ratio,
+  NumericRange(Vec<String>),
+  DigitN(Rep),
+  NonDigitN(Rep),
+  SpaceN(Rep),
+  NonSpaceN(Rep),
+  TabN(Rep),
+  Wordlist(Vec<String>, WordlistOpts),
+  KeywordOf(Vec<String>),
+  CssColor,
+  HtmlEntity,
+  PercentEncoded,
+  PercentEncodedRun,
+  QuotedString,
+  JsonString,
+  JsonNumber,
+  RomanNumeral(RomanCase),
+  Ordinal,
+  LicensePlate(PlateRegion),
+  AnythingUpTo(u32),
+  SomethingUpTo(u32),
+  Unbounded(Unbounded),
+  Doi,
+  ArxivId,
+  Citation,
+  NoMatch,
+  MatchEverything,
+  ByteRange(u8, u8),
+  AsciiPrintable,
+  NonAsciiByte,
+  BytesOf(Vec<u8>),
+  Windows1252Bytes,
+  Identifier(IdentLang),
+  XmlName,
+  DelimitedRecord(Vec<Vec<Step>>, String, bool),
+  BetweenLines(Vec<Step>, Vec<Step>),
+  Column(usize, Option<char>),
+  Columns(usize, usize, Option<char>),
+  CommitSha(Rep),
+  GitRef,
+  BranchName,
+  Hostname(HostnameOpts),
+  DomainName(HostnameOpts),
+  Indented(usize, IndentUnit),
+  IndentationCapture,
+  DedentGuard,
+  Alternation(Alternation),
+  DecimalWith(NumberFormat),
+  DateWith(DateFormat),
+  DurationComponent,
+  Duration,
+  TimezoneOffset,
+  WhitespaceTrimmed,
+  Capture(Vec<Step>),
+  RequireSomewhere(Vec<Step>),
+  Atomic(Vec<Step>),
+  RepeatFragment(Vec<Step>, Rep),
+  LengthBounded(Vec<Step>, u32, u32),
+  Times(Rep),
+  WithTotalLength(u32, u32),
+  Concat(Vec<Step>),
+  Define(String, Vec<Step>),
+  Insert(String),
+  AutoCloseGroups(bool),
+  TolerantWhitespace(bool),
+  DuplicateGroups(DuplicateGroups),
+  #[cfg(feature = "unicode-normalization")]
+  NormalizeLiterals(bool),
+  AsciiOnly,
+}
+
+impl Step {
+  fn apply(&self, v: Verex) -> Verex {
+    match *self {
+      Step::StartOfLine => v.start_of_line(),
+      Step::EndOfLine => v.end_of_line(),
+      Step::Pop => v.pop(),
+      Step::FromRegex(ref pattern) => v.pattern(pattern),
+      Step::Find(ref value) => v.find(value),
+      Step::Raw(ref value) => v.raw(value),
+      Step::Then(ref value) => v.then(value),
+      Step::Maybe(ref value) => v.maybe(value),
+      Step::ThenAnyCase(ref value) => v.then_any_case(value),
+      Step::ThenExactCase(ref value) => v.then_exact_case(value),
+      Step::ThenCasefold(ref value) => v.then_casefold(value),
+      Step::StartsWith(ref value) => v.starts_with(value),
+      Step::EndsWith(ref value) => v.ends_with(value),
+      Step::Anything => v.anything(),
+      #[allow(deprecated)]
+      Step::AnythingButNot(ref value) => v.anything_but_not(value),
+      Step::AnythingBut(ref value) => v.anything_but(value),
+      Step::Something => v.something(),
+      #[allow(deprecated)]
+      Step::SomethingButNot(ref value) => v.something_but_not(value),
+      Step::SomethingBut(ref value) => v.something_but(value),
+      Step::Until(ref value) => v.until(value),
+      Step::UntilAndIncluding(ref value) => v.until_and_including(value),
+      Step::NotStartingWith(ref chars) => v.not_starting_with(chars),
+      Step::NotEndingWith(ref chars) => v.not_ending_with(chars),
+      Step::LineBreak => v.line_break(),
+      Step::Br => v.br(),
+      Step::Tab => v.tab(),
+      Step::Word => v.word(),
+      Step::NonWord => v.non_word(),
+      Step::Digit => v.digit(),
+      Step::NonDigit => v.non_digit(),
+      Step::UnicodeDigit => v.unicode_digit(),
+      Step::AsciiDigit => v.ascii_digit(),
+      Step::Space => v.space(),
+      Step::NonSpace => v.non_space(),
+      Step::OptionalWhitespace => v.optional_whitespace(),
+      Step::RequiredWhitespace => v.required_whitespace(),
+      Step::OptionalHorizontalWhitespace => v.optional_horizontal_whitespace(),
+      Step::RequiredHorizontalWhitespace => v.required_horizontal_whitespace(),
+      Step::Padded(ref steps) => v.padded(|inner| steps.iter().fold(inner, |inner, step| step.apply(inner))),
+      Step::AnyOf(ref value) => v.any_of(value),
+      #[allow(deprecated)]
+      Step::Any(ref value) => v.any(value),
+      Step::Range(ref pairs) => {
+        let pairs: Vec<(&str, &str)> = pairs.iter().map(|(from, to)| (&from[..], &to[..])).collect();
+        v.range(&pairs)
+      },
+      Step::AddModifier(ref modifier) => v.add_modifier(modifier),
+      Step::RemoveModifier(ref modifier) => v.remove_modifier(modifier),
+      Step::SetFlag(flag) => v.set_flag(flag),
+      Step::ClearFlag(flag) => v.clear_flag(flag),
+      Step::WithAnyCase => v.with_any_case(),
+      Step::WithSpecificCase => v.with_specific_case(),
+      Step::SearchMultiline => v.search_multiline(),
+      Step::SearchOneline => v.search_oneline(),
+      #[allow(deprecated)]
+      Step::Multiple(ref value, ref counts) => v.multiple(value, counts),
+      Step::Repeated(ref value, rep) => v.repeated(value, rep),
+      Step::OneOrMore => v.one_or_more(),
+      Step::ZeroOrMore => v.zero_or_more(),
+      Step::Count(count) => v.count(count),
+      Step::CountRange(from, to) => v.count_range(from, to),
+      Step::CountRangeLazy(from, to) => v.count_range_lazy(from, to),
+      Step::AtLeast(from) => v.at_least(from),
+      Step::AtLeastLazy(n) => v.at_least_lazy(n),
+      Step::Lazy => v.lazy(),
+      Step::Possessive => v.possessive(),
+      Step::Or(ref value) => v.or(value),
+      Step::BeginCapture => v.begin_capture(),
+      Step::BeginNamedCapture(ref name) => v.begin_named_capture(name),
+      Step::BeginCaptureTagged(ref tag) => v.begin_capture_tagged(tag),
+      Step::EndCapture => v.end_capture(),
+      Step::Email(strictness) => v.email(strictness),
+      Step::Scheme => v.scheme(),
+      Step::Userinfo => v.userinfo(),
+      Step::Host => v.host(),
+      Step::Port => v.port(),
+      Step::Path => v.path(),
+      Step::Query => v.query(),
+      Step::Fragment => v.fragment(),
+      Step::Url(opts) => v.url(opts),
+      Step::IsoWeekDate => v.iso_week_date(),
+      Step::Hashtag => v.hashtag(),
+      Step::Mention => v.mention(),
+      Step::WordLike(prefix) => v.word_like(prefix),
+      Step::DigitGrouped(group, sep) => v.digit_grouped(group, sep),
+      Step::DigitGroupedOrPlain(group, sep) => v.digit_grouped_or_plain(group, sep),
+      Step::Uuid(opts) => v.uuid(opts),
+      Step::LineComment(ref prefix) => v.line_comment(prefix),
+      Step::LineCommentWithNewline(ref prefix) => v.line_comment_with_newline(prefix),
+      Step::BlockComment(ref open, ref close) => v.block_comment(open, close),
+      Step::Isbn10 => v.isbn10(),
+      Step::Isbn13 => v.isbn13(),
+      Step::Isbn => v.isbn(),
+      Step::Ean13 => v.ean13(),
+      Step::Coordinates(opts) => v.coordinates(opts),
+      Step::PasswordPolicy(ref policy) => v.password_policy(policy),
+      Step::Percent => v.percent(),
+      Step::Ratio => v.ratio(),
+      Step::NumericRange(ref seps) => v.numeric_range(&seps.iter().map(|sep| &sep[..]).collect::<Vec<_>>()),
+      Step::DigitN(rep) => v.digit_n(rep),
+      Step::NonDigitN(rep) => v.non_digit_n(rep),
+      Step::SpaceN(rep) => v.space_n(rep),
+      Step::NonSpaceN(rep) => v.non_space_n(rep),
+      Step::TabN(rep) => v.tab_n(rep),
+      Step::Wordlist(ref words, opts) => v.wordlist(&words.iter().map(|word| &word[..]).collect::<Vec<_>>(), opts),
+      Step::KeywordOf(ref keywords) => v.keyword_of(&keywords.iter().map(|keyword| &keyword[..]).collect::<Vec<_>>()),
+      Step::CssColor => v.css_color(),
+      Step::HtmlEntity => v.html_entity(),
+      Step::PercentEncoded => v.percent_encoded(),
+      Step::PercentEncodedRun => v.percent_encoded_run(),
+      Step::QuotedString => v.quoted_string(),
+      Step::JsonString => v.json_string(),
+      Step::JsonNumber => v.json_number(),
+      Step::RomanNumeral(case) => v.roman_numeral(case),
+      Step::Ordinal => v.ordinal(),
+      Step::LicensePlate(region) => v.license_plate(region),
+      Step::AnythingUpTo(n) => v.anything_up_to(n),
+      Step::SomethingUpTo(n) => v.something_up_to(n),
+      Step::Unbounded(policy) => v.unbounded(policy),
+      Step::Doi => v.doi(),
+      Step::ArxivId => v.arxiv_id(),
+      Step::Citation => v.citation(),
+      Step::NoMatch => v.record(Step::NoMatch, |v| v.atom(NO_MATCH)),
+      Step::MatchEverything => v.record(Step::MatchEverything, |v| v),
+      Step::ByteRange(from, to) => v.byte_range(from, to),
+      Step::AsciiPrintable => v.ascii_printable(),
+      Step::NonAsciiByte => v.non_ascii_byte(),
+      Step::BytesOf(ref bytes) => v.bytes_of(bytes),
+      Step::Windows1252Bytes => v.windows1252_bytes(),
+      Step::Identifier(lang) => v.identifier(lang),
+      Step::XmlName => v.xml_name(),
+      Step::DelimitedRecord(ref fields, ref sep, allow_quoted) => {
+        let fields: Vec<Verex> = fields.iter().map(|steps| Verex::from_steps(steps)).collect();
+        v.delimited_record(&fields, sep, allow_quoted)
+      },
+      Step::BetweenLines(ref start, ref end) => v.between_lines(Verex::from_steps(start), Verex::from_steps(end)),
+      Step::Column(n, sep) => v.column(n, sep),
+      Step::Columns(first, last, sep) => v.columns(first..=last, sep),
+      Step::CommitSha(rep) => v.commit_sha(rep),
+      Step::GitRef => v.git_ref(),
+      Step::BranchName => v.branch_name(),
+      Step::Hostname(opts) => v.hostname(opts),
+      Step::DomainName(opts) => v.domain_name(opts),
+      Step::Indented(level, unit) => v.indented(level, unit),
+      Step::IndentationCapture => v.indentation_capture(),
+      Step::DedentGuard => v.dedent_guard(),
+      Step::Alternation(ref alternation) => v.alternation(alternation),
+      Step::DecimalWith(format) => v.decimal_with(format),
+      Step::DateWith(format) => v.date_with(format),
+      Step::DurationComponent => v.duration_component(),
+      Step::Duration => v.duration(),
+      Step::TimezoneOffset => v.timezone_offset(),
+      Step::WhitespaceTrimmed => v.whitespace_trimmed(),
+      Step::Capture(ref steps) => v.capture(|inner| steps.iter().fold(inner, |inner, step| step.apply(inner))),
+      Step::RequireSomewhere(ref steps) => v.require_somewhere(|inner| steps.iter().fold(inner, |inner, step| step.apply(inner))),
+      Step::Atomic(ref steps) => v.atomic(|inner| steps.iter().fold(inner, |inner, step| step.apply(inner))),
+      Step::RepeatFragment(ref steps, rep) => v.repeat_fragment(|inner| steps.iter().fold(inner, |inner, step| step.apply(inner)), rep),
+      Step::LengthBounded(ref steps, min, max) => v.length_bounded(|inner| steps.iter().fold(inner, |inner, step| step.apply(inner)), min, max),
+      Step::Times(rep) => v.times(rep),
+      Step::WithTotalLength(min, max) => v.with_total_length(min, max),
+      Step::Concat(ref steps) => v.concat(Verex::from_steps(steps)),
+      Step::Define(ref name, ref steps) => v.define(name, |inner| steps.iter().fold(inner, |inner, step| step.apply(inner))),
+      Step::Insert(ref name) => v.insert(name),
+      Step::AutoCloseGroups(enabled) => v.auto_close_groups(enabled),
+      Step::TolerantWhitespace(enabled) => v.tolerant_whitespace(enabled),
+      Step::DuplicateGroups(policy) => v.duplicate_groups(policy),
+      #[cfg(feature = "unicode-normalization")]
+      Step::NormalizeLiterals(enabled) => v.normalize_literals(enabled),
+      Step::AsciiOnly => v.ascii_only(),
+    }
+  }
+
+  pub(crate) fn name(&self) -> &'static str {
+    match *self {
+      Step::StartOfLine => "start_of_line",
+      Step::EndOfLine => "end_of_line",
+      Step::Pop => "pop",
+      Step::FromRegex(_) => "from_regex",
+      Step::Find(_) => "find",
+      Step::Raw(_) => "raw",
+      Step::Then(_) => "then",
+      Step::Maybe(_) => "maybe",
+      Step::ThenAnyCase(_) => "then_any_case",
+      Step::ThenExactCase(_) => "then_exact_case",
+      Step::ThenCasefold(_) => "then_casefold",
+      Step::StartsWith(_) => "starts_with",
+      Step::EndsWith(_) => "ends_with",
+      Step::Anything => "anything",
+      Step::AnythingButNot(_) => "anything_but_not",
+      Step::AnythingBut(_) => "anything_but",
+      Step::Something => "something",
+      Step::SomethingButNot(_) => "something_but_not",
+      Step::SomethingBut(_) => "something_but",
+      Step::Until(_) => "until",
+      Step::UntilAndIncluding(_) => "until_and_including",
+      Step::NotStartingWith(_) => "not_starting_with",
+      Step::NotEndingWith(_) => "not_ending_with",
+      Step::LineBreak => "line_break",
+      Step::Br => "br",
+      Step::Tab => "tab",
+      Step::Word => "word",
+      Step::NonWord => "non_word",
+      Step::Digit => "digit",
+      Step::NonDigit => "non_digit",
+      Step::UnicodeDigit => "unicode_digit",
+      Step::AsciiDigit => "ascii_digit",
+      Step::Space => "space",
+      Step::NonSpace => "non_space",
+      Step::OptionalWhitespace => "optional_whitespace",
+      Step::RequiredWhitespace => "required_whitespace",
+      Step::OptionalHorizontalWhitespace => "optional_horizontal_whitespace",
+      Step::RequiredHorizontalWhitespace => "required_horizontal_whitespace",
+      Step::Padded(_) => "padded",
+      Step::AnyOf(_) => "any_of",
+      Step::Any(_) => "any",
+      Step::Range(_) => "range",
+      Step::AddModifier(_) => "add_modifier",
+      Step::RemoveModifier(_) => "remove_modifier",
+      Step::SetFlag(_) => "set_flag",
+      Step::ClearFlag(_) => "clear_flag",
+      Step::WithAnyCase => "with_any_case",
+      Step::WithSpecificCase => "with_specific_case",
+      Step::SearchMultiline => "search_multiline",
+      Step::SearchOneline => "search_oneline",
+      Step::Multiple(..) => "multiple",
+      Step::Repeated(..) => "repeated",
+      Step::OneOrMore => "one_or_more",
+      Step::ZeroOrMore => "zero_or_more",
+      Step::Count(_) => "count",
+      Step::CountRange(..) => "count_range",
+      Step::CountRangeLazy(..) => "count_range_lazy",
+      Step::AtLeast(_) => "at_least",
+      Step::AtLeastLazy(_) => "at_least_lazy",
+      Step::Lazy => "lazy",
+      Step::Possessive => "possessive",
+      Step::Or(_) => "or",
+      Step::BeginCapture => "begin_capture",
+      Step::BeginNamedCapture(_) => "begin_named_capture",
+      Step::BeginCaptureTagged(_) => "begin_capture_tagged",
+      Step::EndCapture => "end_capture",
+      Step::Email(_) => "email",
+      Step::Scheme => "scheme",
+      Step::Userinfo => "userinfo",
+      Step::Host => "host",
+      Step::Port => "port",
+      Step::Path => "path",
+      Step::Query => "query",
+      Step::Fragment => "fragment",
+      Step::Url(_) => "url",
+      Step::IsoWeekDate => "iso_week_date",
+      Step::Hashtag => "hashtag",
+      Step::Mention => "mention",
+      Step::WordLike(_) => "word_like",
+      Step::DigitGrouped(..) => "digit_grouped",
+      Step::DigitGroupedOrPlain(..) => "digit_grouped_or_plain",
+      Step::Uuid(_) => "uuid",
+      Step::LineComment(_) => "line_comment",
+      Step::LineCommentWithNewline(_) => "line_comment_with_newline",
+      Step::BlockComment(..) => "block_comment",
+      Step::Isbn10 => "isbn10",
+      Step::Isbn13 => "isbn13",
+      Step::Isbn => "isbn",
+      Step::Ean13 => "ean13",
+      Step::Coordinates(_) => "coordinates",
+      Step::PasswordPolicy(_) => "password_policy",
+      Step::Percent => "percent",
+      Step::Ratio => "ratio",
+      Step::NumericRange(_) => "numeric_range",
+      Step::DigitN(_) => "digit_n",
+      Step::NonDigitN(_) => "non_digit_n",
+      Step::SpaceN(_) => "space_n",
+      Step::NonSpaceN(_) => "non_space_n",
+      Step::TabN(_) => "tab_n",
+      Step::Wordlist(..) => "wordlist",
+      Step::KeywordOf(_) => "keyword_of",
+      Step::CssColor => "css_color",
+      Step::HtmlEntity => "html_entity",
+      Step::PercentEncoded => "percent_encoded",
+      Step::PercentEncodedRun => "percent_encoded_run",
+      Step::QuotedString => "quoted_string",
+      Step::JsonString => "json_string",
+      Step::JsonNumber => "json_number",
+      Step::RomanNumeral(_) => "roman_numeral",
+      Step::Ordinal => "ordinal",
+      Step::LicensePlate(_) => "license_plate",
+      Step::AnythingUpTo(_) => "anything_up_to",
+      Step::SomethingUpTo(_) => "something_up_to",
+      Step::Unbounded(_) => "unbounded",
+      Step::Doi => "doi",
+      Step::ArxivId => "arxiv_id",
+      Step::Citation => "citation",
+      Step::NoMatch => "no_match",
+      Step::MatchEverything => "match_everything",
+      Step::ByteRange(..) => "byte_range",
+      Step::AsciiPrintable => "ascii_printable",
+      Step::NonAsciiByte => "non_ascii_byte",
+      Step::BytesOf(_) => "bytes_of",
+      Step::Windows1252Bytes => "windows1252_bytes",
+      Step::Identifier(_) => "identifier",
+      Step::XmlName => "xml_name",
+      Step::DelimitedRecord(..) => "delimited_record",
+      Step::BetweenLines(..) => "between_lines",
+      Step::Column(..) => "column",
+      Step::Columns(..) => "columns",
+      Step::CommitSha(_) => "commit_sha",
+      Step::GitRef => "git_ref",
+      Step::BranchName => "branch_name",
+      Step::Hostname(_) => "hostname",
+      Step::DomainName(_) => "domain_name",
+      Step::Indented(..) => "indented",
+      Step::IndentationCapture => "indentation_capture",
+      Step::DedentGuard => "dedent_guard",
+      Step::Alternation(_) => "alternation",
+      Step::DecimalWith(_) => "decimal_with",
+      Step::DateWith(_) => "date_with",
+      Step::DurationComponent => "duration_component",
+      Step::Duration => "duration",
+      Step::TimezoneOffset => "timezone_offset",
+      Step::WhitespaceTrimmed => "whitespace_trimmed",
+      Step::Capture(_) => "capture",
+      Step::RequireSomewhere(_) => "require_somewhere",
+      Step::Atomic(_) => "atomic",
+      Step::RepeatFragment(..) => "repeat_fragment",
+      Step::LengthBounded(..) => "length_bounded",
+      Step::Times(_) => "times",
+      Step::WithTotalLength(..) => "with_total_length",
+      Step::Concat(_) => "concat",
+      Step::Define(..) => "define",
+      Step::Insert(_) => "insert",
+      Step::AutoCloseGroups(_) => "auto_close_groups",
+      Step::TolerantWhitespace(_) => "tolerant_whitespace",
+      Step::DuplicateGroups(_) => "duplicate_groups",
+      #[cfg(feature = "unicode-normalization")]
+      Step::NormalizeLiterals(_) => "normalize_literals",
+      Step::AsciiOnly => "ascii_only",
+    }
+  }
+}
+
+fn join_steps(steps: &[Step]) -> String {
+  steps.iter().map(|step| step.to_string()).collect::<Vec<_>>().join(" -> ")
+}
+
+impl fmt::Display for Step {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Step::Find(ref value) | Step::Raw(ref value) | Step::Then(ref value) | Step::Maybe(ref value) |
+      Step::ThenAnyCase(ref value) | Step::ThenExactCase(ref value) | Step::ThenCasefold(ref value) |
+      Step::StartsWith(ref value) | Step::EndsWith(ref value) |
+      Step::AnythingBut(ref value) | Step::SomethingBut(ref value) |
+      Step::AnythingButNot(ref value) | Step::SomethingButNot(ref value) |
+      Step::Until(ref value) | Step::UntilAndIncluding(ref value) |
+      Step::NotStartingWith(ref value) | Step::NotEndingWith(ref value) |
+      Step::AnyOf(ref value) | Step::Any(ref value) |
+      Step::AddModifier(ref value) | Step::RemoveModifier(ref value) |
+      Step::Or(ref value) | Step::BeginNamedCapture(ref value) |
+      Step::BeginCaptureTagged(ref value) | Step::LineComment(ref value) |
+      Step::LineCommentWithNewline(ref value) | Step::Insert(ref value) => write!(f, "{}({:?})", self.name(), value),
+      Step::Padded(ref steps) => write!(f, "{}({})", self.name(), join_steps(steps)),
+      Step::Range(ref pairs) => write!(f, "{}({:?})", self.name(), pairs),
+      Step::Multiple(ref value, ref counts) => write!(f, "{}({:?}, {:?})", self.name(), value, counts),
+      Step::Count(n) | Step::AtLeast(n) | Step::AtLeastLazy(n) => write!(f, "{}({})", self.name(), n),
+      Step::CountRange(from, to) | Step::CountRangeLazy(from, to) => write!(f, "{}({}, {})", self.name(), from, to),
+      Step::Email(strictness) => write!(f, "{}({:?})", self.name(), strictness),
+      Step::Url(opts) => write!(f, "{}({:?})", self.name(), opts),
+      Step::Capture(ref steps) => write!(f, "{}({})", self.name(), join_steps(steps)),
+      Step::AutoCloseGroups(enabled) | Step::TolerantWhitespace(enabled) => write!(f, "{}({})", self.name(), enabled),
+      #[cfg(feature = "unicode-normalization")]
+      Step::NormalizeLiterals(enabled) => write!(f, "{}({})", self.name(), enabled),
+      Step::WordLike(prefix) => write!(f, "{}({:?})", self.name(), prefix),
+      Step::RepeatFragment(ref steps, rep) => write!(f, "{}({}, {:?})", self.name(), join_steps(steps), rep),
+      Step::Times(rep) | Step::DigitN(rep) | Step::NonDigitN(rep) | Step::SpaceN(rep) |
+      Step::NonSpaceN(rep) | Step::TabN(rep) | Step::CommitSha(rep) => write!(f, "{}({:?})", self.name(), rep),
+      Step::FromRegex(ref pattern) => write!(f, "{}({:?})", self.name(), pattern),
+      Step::DigitGrouped(group, sep) | Step::DigitGroupedOrPlain(group, sep) => write!(f, "{}({}, {:?})", self.name(), group, sep),
+      Step::Uuid(opts) => write!(f, "{}({:?})", self.name(), opts),
+      Step::Coordinates(opts) => write!(f, "{}({:?})", self.name(), opts),
+      Step::SetFlag(flag) | Step::ClearFlag(flag) => write!(f, "{}({:?})", self.name(), flag),
+      Step::BlockComment(ref open, ref close) => write!(f, "{}({:?}, {:?})", self.name(), open, close),
+      Step::Concat(ref steps) => write!(f, "{}({})", self.name(), join_steps(steps)),
+      Step::RequireSomewhere(ref steps) => write!(f, "{}({})", self.name(), join_steps(steps)),
+      Step::PasswordPolicy(ref policy) => write!(f, "{}({:?})", self.name(), policy),
+      Step::NumericRange(ref seps) => write!(f, "{}({:?})", self.name(), seps),
+      Step::Wordlist(ref words, opts) => write!(f, "{}({:?}, {:?})", self.name(), words, opts),
+      Step::DelimitedRecord(ref fields, ref sep, allow_quoted) => {
+        let fields: Vec<String> = fields.iter().map(|steps| format!("[{}]", join_steps(steps))).collect();
+        write!(f, "{}({}, {:?}, {})", self.name(), fields.join(", "), sep, allow_quoted)
+      },
+      Step::Column(n, sep) => write!(f, "{}({}, {:?})", self.name(), n, sep),
+      Step::Columns(first, last, sep) => write!(f, "{}({}..={}, {:?})", self.name(), first, last, sep),
+      Step::Hostname(opts) | Step::DomainName(opts) => write!(f, "{}({:?})", self.name(), opts),
+      Step::Indented(level, unit) => write!(f, "{}({}, {:?})", self.name(), level, unit),
+      Step::Alternation(ref alternation) => write!(f, "{}({:?})", self.name(), alternation),
+      Step::DecimalWith(format) => write!(f, "{}({:?})", self.name(), format),
+      Step::DateWith(format) => write!(f, "{}({:?})", self.name(), format),
+      Step::Define(ref name, ref steps) => write!(f, "{}({:?}, {})", self.name(), name, join_steps(steps)),
+      Step::KeywordOf(ref keywords) => write!(f, "{}({:?})", self.name(), keywords),
+      Step::RomanNumeral(case) => write!(f, "{}({:?})", self.name(), case),
+      Step::Atomic(ref steps) => write!(f, "{}({})", self.name(), join_steps(steps)),
+      Step::DuplicateGroups(policy) => write!(f, "{}({:?})", self.name(), policy),
+      Step::Repeated(ref value, rep) => write!(f, "{}({:?}, {:?})", self.name(), value, rep),
+      Step::LengthBounded(ref steps, min, max) => write!(f, "{}({}, {}, {})", self.name(), join_steps(steps), min, max),
+      Step::WithTotalLength(min, max) => write!(f, "{}({}, {})", self.name(), min, max),
+      Step::LicensePlate(region) => write!(f, "{}({:?})", self.name(), region),
+      Step::AnythingUpTo(n) | Step::SomethingUpTo(n) => write!(f, "{}({})", self.name(), n),
+      Step::Unbounded(policy) => write!(f, "{}({:?})", self.name(), policy),
+      Step::ByteRange(from, to) => write!(f, "{}({:#04x}, {:#04x})", self.name(), from, to),
+      Step::BytesOf(ref bytes) => write!(f, "{}({:?})", self.name(), bytes),
+      Step::Identifier(lang) => write!(f, "{}({:?})", self.name(), lang),
+      Step::BetweenLines(ref start, ref end) => write!(f, "{}([{}], [{}])", self.name(), join_steps(start), join_steps(end)),
+      _ => write!(f, "{}()", self.name()),
+    }
+  }
+}
+
+/// How many times `Verex::repeat_fragment` and `Verex::times` repeat their fragment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Rep {
+  Optional,
+  ZeroOrMore,
+  OneOrMore,
+  Exactly(u32),
+  AtLeast(u32),
+  Between(u32, u32),
+}
+
+impl Rep {
+  /// The `Rep` that the counts of the deprecated `Verex::multiple` stand for, or `None` if a
+  /// count it uses is negative.
+  pub(crate) fn from_counts(counts: &[i32]) -> Option<Rep> {
+    let count = |i: usize| u32::try_from(counts[i]).ok();
+    match counts.len() {
+      0 => Some(Rep::OneOrMore),
+      1 => count(0).map(Rep::Exactly),
+      _ => Some(Rep::Between(count(0)?, count(1)?)),
+    }
+  }
+
+  pub(crate) fn quantifier(self) -> String {
+    match self {
+      Rep::Optional => "?".to_string(),
+      Rep::ZeroOrMore => "*".to_string(),
+      Rep::OneOrMore => "+".to_string(),
+      Rep::Exactly(n) => format!("{{{}}}", n),
+      Rep::AtLeast(n) => format!("{{{},}}", n),
+      Rep::Between(from, to) => format!("{{{},{}}}", from, to),
+    }
+  }
+}
+
+/// What `Verex::duplicate_groups` does when several capture groups share a name, as when a
+/// preset such as `date_with` is embedded twice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DuplicateGroups {
+  /// Report the pattern as `VerexError::DuplicateGroupName`.
+  #[default]
+  Reject,
+  /// Keep the first group's name and rename the later ones `name_2`, `name_3` and so on,
+  /// skipping names already in use.
+  Suffix,
+}
+
+/// Where the capture groups of fragments joined with `Verex::concat` and `Verex::alternation`
+/// ended up in the composed pattern, so that group indices taken from a fragment on its own can
+/// be translated. Fragments are numbered from 0 in the order they were added, alternatives in
+/// the order they were added to their `Alternation` rather than the order they are tried in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompositionMap {
+  /// The index of the group before the first group of each fragment, and its number of groups.
+  fragments: Vec<(usize, usize)>,
+}
+
+impl CompositionMap {
+  /// The number of fragments composed.
+  pub fn len(&self) -> usize {
+    self.fragments.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.fragments.is_empty()
+  }
+
+  /// The index in the composed pattern of group `local` of `fragment`, counting from 1 as in the
+  /// fragment on its own. The whole match of a fragment, group 0, is not a group of the
+  /// composed pattern.
+  pub fn index(&self, fragment: usize, local: usize) -> Option<usize> {
+    let (offset, count) = *self.fragments.get(fragment)?;
+    if local == 0 || local > count {
+      return None;
+    }
+    Some(offset + local)
+  }
+
+  /// The indices in the composed pattern of all the groups of `fragment`.
+  pub fn indices(&self, fragment: usize) -> Option<Range<usize>> {
+    self.fragments.get(fragment).map(|&(offset, count)| offset + 1..offset + 1 + count)
+  }
+}
+
+/// What `Verex::unbounded` does with `anything`, `something`, `anything_but`,
+/// `something_but`, `zero_or_more`, `one_or_more` and the block of `between_lines`, which
+/// match runs of any length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Unbounded {
+  #[default]
+  Allow,
+  /// Report them as `VerexError::UnboundedRepetition`.
+  Forbid,
+  /// Match at most this many repetitions with them, or once for those that need at least one.
+  Cap(u32),
+}
+
+/// An inline flag for `Verex::set_flag` and `Verex::clear_flag`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Flag {
+  CaseInsensitive,
+  MultiLine,
+  DotMatchesNewline,
+  SwapGreed,
+  Unicode,
+  IgnoreWhitespace,
+  CRLF,
+}
+
+impl Flag {
+  /// The letter the regex syntax uses for this flag.
+  pub fn letter(self) -> char {
+    match self {
+      Flag::CaseInsensitive => 'i',
+      Flag::MultiLine => 'm',
+      Flag::DotMatchesNewline => 's',
+      Flag::SwapGreed => 'U',
+      Flag::Unicode => 'u',
+      Flag::IgnoreWhitespace => 'x',
+      Flag::CRLF => 'R',
+    }
+  }
+
+  /// The flag spelled by `letter`, if there is one.
+  pub fn from_letter(letter: char) -> Option<Flag> {
+    match letter {
+      'i' => Some(Flag::CaseInsensitive),
+      'm' => Some(Flag::MultiLine),
+      's' => Some(Flag::DotMatchesNewline),
+      'U' => Some(Flag::SwapGreed),
+      'u' => Some(Flag::Unicode),
+      'x' => Some(Flag::IgnoreWhitespace),
+      'R' => Some(Flag::CRLF),
+      _ => None,
+    }
+  }
+}
+
+/// A pattern under construction, built up by chaining combinators.
+///
+/// Combinators that add text to match, such as `then`, `maybe`, `starts_with`, `until` and
+/// `repeated`, add nothing for an empty value; the step is still recorded, and a quantifier
+/// that follows applies to what came before. Combinators that build a character class, such as
+/// `any_of`, `anything_but`, `not_starting_with` and `range`, have nothing to match with an
+/// empty argument, which `try_as_regex` reports as `VerexError::EmptyClass`, as it reports an
+/// empty `or` as `VerexError::EmptyAlternation`. `wordlist` and `alternation` without any
+/// alternatives match nothing.
+#[derive(Clone, Debug)]
+pub struct Verex {
+  prefix: String,
+  multiline: bool,
+  open_groups: usize,
+  auto_close_groups: bool,
+  pub(crate) ascii_only: bool,
+  tolerant_whitespace: bool,
+  #[cfg(feature = "unicode-normalization")]
+  pub(crate) normalize_literals: bool,
+  duplicate_groups: DuplicateGroups,
+  unbounded: Unbounded,
+  composition: CompositionMap,
+  tokens: Vec<Token>,
+  /// The index of the step that left each token as it is, for `to_pretty_string`. Tokens past
+  /// its end were added or changed by the step being recorded.
+  origins: Vec<usize>,
+  /// Whether a step is being recorded, which takes what the combinators it calls add as its own.
+  recording: bool,
+  suffix: String,
+  steps: Vec<Step>,
+  definitions: HashMap<String, Vec<Step>>,
+  /// The definitions being inserted, innermost last, so that a cycle stops the expansion.
+  expanding: Vec<String>,
+}
+
+impl Verex {
+  pub fn new() -> Verex {
+    Verex { prefix: String::new(), multiline: false, open_groups: 0, auto_close_groups: false, ascii_only: false, tolerant_whitespace: false, #[cfg(feature = "unicode-normalization")] normalize_literals: false, duplicate_groups: DuplicateGroups::Reject, unbounded: Unbounded::Allow, composition: CompositionMap::default(), tokens: Vec::new(), origins: Vec::new(), recording: false, suffix: String::new(), steps: Vec::new(), definitions: HashMap::new(), expanding: Vec::new() }
+  }
+
+  pub fn from_steps(steps: &[Step]) -> Verex {
+    steps.iter().fold(Verex::new(), |v, step| step.apply(v))
+  }
+
+  /// Starts an expression from an existing regex, whose pattern is kept as an opaque group.
+  pub fn from_regex(re: &Regex) -> Verex {
+    Verex::new().pattern(re.as_str())
+  }
+
+  /// Reads a pattern, such as one rendered by `as_string`, back into the steps that build it,
+  /// after checking that it compiles. Literals, groups, captures, quantifiers, alternations,
+  /// anchors, flags and the combinators whose rendering is found as is are recovered, so that
+  /// combinators added afterwards compose as they did on the original; anything else, such as a
+  /// dot or a class `any_of` would not have rendered, is kept as a `from_regex` fragment. Either
+  /// way the expression matches what the pattern matches.
+  pub fn parse(pattern: &str) -> Result<Verex, VerexError> {
+    Regex::new(pattern).map_err(|error| VerexError::Compile { pattern: pattern.to_string(), error })?;
+    let steps = parse::steps(pattern).unwrap_or_else(|| vec![Step::FromRegex(pattern.to_string())]);
+    Ok(Verex::from_steps(&steps))
+  }
+
+  /// An expression that never matches, not even the empty text, as an `alternation` with no
+  /// alternatives does. Whatever is joined to it with `concat` cannot match either.
+  pub fn no_match() -> Verex {
+    Step::NoMatch.apply(Verex::new())
+  }
+
+  /// An expression that matches every text, with an empty match at its start, as joining no
+  /// fragments with `collect` does. Joining a fragment to it with `concat` gives an expression
+  /// that matches what the fragment matches.
+  pub fn match_everything() -> Verex {
+    Step::MatchEverything.apply(Verex::new())
+  }
+
+  /// Starts an expression that matches text beginning with `value`. See `starts_with`.
+  pub fn starting_with(value: &str) -> Verex {
+    Verex::new().starts_with(value)
+  }
+
+  /// Starts an expression that matches text ending with `value`. See `ends_with`.
+  pub fn ending_with(value: &str) -> Verex {
+    Verex::new().ends_with(value)
+  }
+
+  /// Starts an expression matching any of `words`. See `wordlist`.
+  pub fn from_wordlist(words: &[&str]) -> Verex {
+    Verex::new().wordlist(words, WordlistOpts::default())
+  }
+
+  fn pattern(self, pattern: &str) -> Verex {
+    self.record(Step::FromRegex(pattern.to_string()), |v| v.atom(&format!("(?:{})", pattern)))
+  }
+
+  pub fn steps(&self) -> &[Step] {
+    &self.steps
+  }
+
+  pub(crate) fn record<F>(mut self, step: Step, f: F) -> Verex where F: FnOnce(Verex) -> Verex {
+    let mut steps = mem::take(&mut self.steps);
+    let recording = mem::replace(&mut self.recording, true);
+    let mut v = f(self);
+    steps.push(step);
+    v.steps = steps;
+    v.recording = recording;
+    if !recording {
+      let index = v.steps.len() - 1;
+      v.origins.truncate(v.tokens.len());
+      v.origins.resize(v.tokens.len(), index);
+    }
+    v
+  }
+
+  pub fn start_of_line(self) -> Self {
+    self.record(Step::StartOfLine, |mut v| {
+      v.prefix.push('^');
+      v
+    })
+  }
+
+  pub fn end_of_line(self) -> Self {
+    self.record(Step::EndOfLine, |mut v| {
+      v.suffix.push('$');
+      v
+    })
+  }
+
+  fn push(mut self, token: Token) -> Self {
+    self.tokens.push(token);
+    self
+  }
+
+  pub(crate) fn add(self, value: &str) -> Self {
+    self.push(Token::Raw(value.to_string()))
+  }
+
+  pub(crate) fn atom(self, value: &str) -> Self {
+    self.push(Token::Atom(value.to_string()))
+  }
+
+  /// Appends a pattern of bytes, which only `try_as_bytes_regex` accepts if it can match a byte
+  /// outside ASCII.
+  pub(crate) fn bytes_atom(self, value: &str, non_ascii: bool) -> Self {
+    self.push(if non_ascii { Token::Bytes(value.to_string()) } else { Token::Atom(value.to_string()) })
+  }
+
+  pub(crate) fn quantify(self, value: &str) -> Self {
+    self.push(Token::Quantifier(value.to_string()))
+  }
+
+  pub(crate) fn class(&self, ascii: &str, unicode: &str) -> String {
+    format!("(?:{})", if self.ascii_only { ascii } else { unicode })
+  }
+
+  pub(crate) fn scope(&self) -> Verex {
+    Verex { ascii_only: self.ascii_only, tolerant_whitespace: self.tolerant_whitespace, #[cfg(feature = "unicode-normalization")] normalize_literals: self.normalize_literals, unbounded: self.unbounded, definitions: self.definitions.clone(), expanding: self.expanding.clone(), ..Verex::new() }
+  }
+
+  /// Takes the tokens that a step on everything built so far applies to: those added since the
+  /// innermost group still open began, or else all of them along with the parentheses `or` keeps
+  /// in the prefix and suffix. Flags, leading `^` and trailing `$` anchors and open groups stay
+  /// where they are.
+  fn take_body(&mut self) -> Vec<Token> {
+    if let Some(i) = innermost_open_group(&self.tokens) {
+      self.origins.truncate(i + 1);
+      return self.tokens.split_off(i + 1);
+    }
+    let anchors = self.prefix.len() - self.prefix.trim_start_matches('^').len();
+    let prefix = self.prefix.split_off(anchors);
+    let kept = self.suffix.trim_end_matches('$').len();
+    let suffix: String = self.suffix.drain(..kept).collect();
+    let mut body = Vec::new();
+    if !prefix.is_empty() {
+      body.push(Token::Raw(prefix));
+    }
+    body.append(&mut self.tokens);
+    self.origins.clear();
+    if !suffix.is_empty() {
+      body.push(Token::Raw(suffix));
+    }
+    body
+  }
+
+  fn flags(&self) -> &'static str {
+    if self.multiline { "(?m)" } else { "" }
+  }
+
+  fn into_tokens(self) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    if self.multiline {
+      tokens.push(Token::Raw(self.flags().to_string()));
+    }
+    if !self.prefix.is_empty() {
+      tokens.push(Token::Raw(self.prefix));
+    }
+    tokens.extend(self.tokens);
+    if !self.suffix.is_empty() {
+      tokens.push(Token::Raw(self.suffix));
+    }
+    tokens
+  }
+
+  /// Undoes the last combinator, whatever tokens, anchors or flags it added, by rebuilding the
+  /// expression from the steps before it. The undone step is not recorded, so `steps` lists
+  /// only those that remain.
+  pub fn pop(mut self) -> Self {
+    self.steps.pop();
+    Verex::from_steps(&self.steps)
+  }
+
+  pub fn find(self, value: &str) -> Self {
+    self.record(Step::Find(value.to_string()), |v| {
+      let value = &v.literal(value);
+      if value.is_empty() {
+        v
+      } else if v.tolerant_whitespace && value.contains(' ') {
+        let mut pattern = String::from("(?:");
+        for (i, c) in value.char_indices() {
+          match c {
+            ' ' if value[..i].ends_with(' ') => {},
+            ' ' => pattern.push_str(r"\s+"),
+            _ => pattern.push(c),
+          }
+        }
+        pattern.push(')');
+        v.atom(&pattern)
+      } else {
+        let mut v = v;
+        delimit_until(&mut v.tokens, &until_lookahead(value));
+        v.push(Token::Literal(value.to_string()))
+      }
+    })
+  }
+
+  /// Appends `value` to the pattern as it is, without escaping. Use `escape` and `escape_class`
+  /// for the parts that should match literally.
+  pub fn raw(self, value: &str) -> Self {
+    self.record(Step::Raw(value.to_string()), |v| v.add(value))
+  }
+
+  pub fn then(self, value: &str) -> Self {
+    self.record(Step::Then(value.to_string()), |v| v.find(value))
+  }
+
+  pub fn maybe(self, value: &str) -> Self {
+    self.record(Step::Maybe(value.to_string()), |v| {
+      let value = &v.literal(value);
+      if value.is_empty() { v } else { v.atom(&format!("(?:{})", value)).quantify("?") }
+    })
+  }
+
+  /// Matches `value` literally, ignoring case for this value only.
+  pub fn then_any_case(self, value: &str) -> Self {
+    self.record(Step::ThenAnyCase(value.to_string()), |v| {
+      let value = &v.literal(value);
+      if value.is_empty() { v } else { v.atom(&format!("(?i:{})", regex::escape(value))) }
+    })
+  }
+
+  /// Matches `value` literally with its exact case, even after `with_any_case`.
+  pub fn then_exact_case(self, value: &str) -> Self {
+    self.record(Step::ThenExactCase(value.to_string()), |v| {
+      let value = &v.literal(value);
+      if value.is_empty() { v } else { v.atom(&format!("(?-i:{})", regex::escape(value))) }
+    })
+  }
+
+  /// Matches `value` literally in any case without a case-insensitive flag, by matching each
+  /// ASCII letter with a class of both its cases, such as `[Ss][Tt][Rr]` for `"str"`. Other
+  /// letters that have cases fall back to a case-insensitive group of their own, `(?i:é)`, which
+  /// uses Unicode simple case folding; `with_any_case` and `with_specific_case` change neither.
+  pub fn then_casefold(self, value: &str) -> Self {
+    self.record(Step::ThenCasefold(value.to_string()), |v| {
+      let value = &v.literal(value);
+      let pattern: String = value.chars().map(|c| {
+        if c.is_ascii_alphabetic() {
+          format!("[{}{}]", c.to_ascii_uppercase(), c.to_ascii_lowercase())
+        } else if c.is_lowercase() || c.is_uppercase() {
+          format!("(?i:{})", regex::escape(&c.to_string()))
+        } else {
+          regex::escape(&c.to_string())
+        }
+      }).collect();
+      if value.is_empty() { v } else { v.atom(&format!("(?:{})", pattern)) }
+    })
+  }
+  /// Anchors the pattern to the start of the line and matches `value` literally there.
+  pub fn starts_with(self, value: &str) -> Self {
+    self.record(Step::StartsWith(value.to_string()), |v| v.start_of_line().escaped_text(value))
+  }
+
+  /// Matches `value` literally followed by the end of the line. The anchor is matched where it
+  /// is added, so it is part of an `or` branch or group like the combinators before it.
+  pub fn ends_with(self, value: &str) -> Self {
+    self.record(Step::EndsWith(value.to_string()), |v| v.escaped_text(value).atom("$"))
+  }
+
+
+  /// `value` as literal combinators match it: in NFC with `normalize_literals`.
+  fn literal<'a>(&self, value: &'a str) -> Cow<'a, str> {
+    #[cfg(feature = "unicode-normalization")]
+    {
+      if self.normalize_literals {
+        return normalize::nfc(value);
+      }
+    }
+    Cow::Borrowed(value)
+  }
+
+  /// Matches `value` escaped, or adds nothing for an empty `value`.
+  fn escaped_text(self, value: &str) -> Self {
+    let value = &self.literal(value);
+    if value.is_empty() { self } else { self.atom(&format!("(?:{})", regex::escape(value))) }
+  }
+
+  pub fn anything(self) -> Self {
+    self.record(Step::Anything, |v| {
+      let pattern = format!("(?:.{})", v.unbounded_quantifier("*"));
+      v.atom(&pattern)
+    })
+  }
+
+  /// Like `anything`, but matches at most `n` characters.
+  pub fn anything_up_to(self, n: u32) -> Verex {
+    self.record(Step::AnythingUpTo(n), |v| v.atom(&format!("(?:.{{0,{}}})", n)))
+  }
+
+  /// Matches any run of characters other than those in `value`, which is a class body as in
+  /// `any_of`, including an empty run.
+  pub fn anything_but(self, value: &str) -> Verex {
+    self.record(Step::AnythingBut(value.to_string()), |v| {
+      let pattern = format!("(?:[^{}]{})", value, v.unbounded_quantifier("*"));
+      v.atom(&pattern)
+    })
+  }
+
+  #[deprecated(note = "renamed to `anything_but`")]
+  pub fn anything_but_not(self, value: &str) -> Verex {
+    self.record(Step::AnythingButNot(value.to_string()), |v| v.anything_but(value))
+  }
+
+  pub fn something(self) -> Verex {
+    self.record(Step::Something, |v| {
+      let pattern = format!("(?:.{})", v.unbounded_quantifier("+"));
+      v.atom(&pattern)
+    })
+  }
+
+  /// Like `something`, but matches at most `n` characters. With `n` of 0 nothing matches.
+  pub fn something_up_to(self, n: u32) -> Verex {
+    self.record(Step::SomethingUpTo(n), |v| v.atom(&format!("(?:.{{1,{}}})", n)))
+  }
+
+  /// Like `anything_but`, but matches at least one character.
+  pub fn something_but(self, value: &str) -> Verex {
+    self.record(Step::SomethingBut(value.to_string()), |v| {
+      let pattern = format!("(?:[^{}]{})", value, v.unbounded_quantifier("+"));
+      v.atom(&pattern)
+    })
+  }
+
+  #[deprecated(note = "renamed to `something_but`")]
+  pub fn something_but_not(self, value: &str) -> Verex {
+    self.record(Step::SomethingButNot(value.to_string()), |v| v.something_but(value))
+  }
+
+  /// Consumes everything up to, but not including, the next occurrence of `value` on the same
+  /// line, like `anything` does not cross line breaks either.
+  ///
+  /// For a single character this is a negated class. Longer values need a lookahead, which only
+  /// the `fancy` backend has. The default backend accepts them only when `then(value)` follows,
+  /// possibly after closing groups, as in `until("://").then("://")`: the lookahead then falls
+  /// back to a lazy `(?:.*?)`, which stops at the same place. Otherwise `try_as_regex` reports
+  /// `VerexError::RequiresFancyBackend`.
+  pub fn until(self, value: &str) -> Verex {
+    self.record(Step::Until(value.to_string()), |v| {
+      let value = &v.literal(value);
+      let mut chars = value.chars();
+      match (chars.next(), chars.next()) {
+        (None, _) => v,
+        (Some(c), None) => v.atom(&format!("(?:[^{}\\n]*)", regex::escape(&c.to_string()))),
+        _ => v.push(Token::Fancy { pattern: until_lookahead(value), fallback: None }),
+      }
+    })
+  }
+
+  /// Matches the indentation at the start of a line, exactly `level` times `unit`. Like
+  /// `search_multiline`, this makes `^` and `$` match at every line for the whole pattern, so it
+  /// can be combined with `start_of_line` and `end_of_line`.
+  ///
+  /// Rejecting deeper indentation needs a lookahead, which only the `fancy` backend has; the
+  /// default backend relies on what follows to not start with a blank, as in
+  /// `indented(2, IndentUnit::Spaces(2)).then("- ")`.
+  pub fn indented(self, level: usize, unit: IndentUnit) -> Verex {
+    self.record(Step::Indented(level, unit), |mut v| {
+      v.multiline = true;
+      let indent = format!("^(?:{}){{{}}}", unit.pattern(), level);
+      v.push(Token::Fancy { pattern: format!("(?:{}(?![ \\t]))", indent), fallback: Some(format!("(?:{})", indent)) })
+    })
+  }
+
+  /// Matches the blanks at the start of a line, capturing them as `indent`. Makes the whole
+  /// pattern multiline, like `indented`.
+  pub fn indentation_capture(self) -> Verex {
+    self.record(Step::IndentationCapture, |mut v| {
+      v.multiline = true;
+      v.atom("(?:^(?P<indent>[ \\t]*))")
+    })
+  }
+
+  /// Asserts that this is the start of a line that does not start with a blank. Makes the whole
+  /// pattern multiline, like `indented`. This needs a lookahead, so only the `fancy` backend can
+  /// compile it.
+  pub fn dedent_guard(self) -> Verex {
+    self.record(Step::DedentGuard, |mut v| {
+      v.multiline = true;
+      v.push(Token::Fancy { pattern: "(?:^(?![ \\t]))".to_string(), fallback: None })
+    })
+  }
+
+  /// Consumes everything up to and including the next occurrence of `value` on the same line.
+  pub fn until_and_including(self, value: &str) -> Verex {
+    self.record(Step::UntilAndIncluding(value.to_string()), |v| {
+      let value = &v.literal(value);
+      let mut chars = value.chars();
+      match (chars.next(), chars.next()) {
+        (None, _) => v,
+        (Some(c), None) => {
+          let c = regex::escape(&c.to_string());
+          v.atom(&format!("(?:[^{}\\n]*{})", c, c))
+        },
+        _ => v.atom(&format!("(?:.*?{})", regex::escape(value))),
+      }
+    })
+  }
+
+  /// Matches one character that is not in `chars`, typically right after `start_of_line`.
+  ///
+  /// Unlike a negative lookahead such as `(?!\d)`, this consumes the character, so it cannot
+  /// match at the end of the text and the following combinators start after it. Lookarounds
+  /// need the `fancy` backend; see `as_fancy_regex`.
+  pub fn not_starting_with(self, chars: &str) -> Verex {
+    self.record(Step::NotStartingWith(chars.to_string()), |v| v.atom(&format!("(?:[^{}])", regex::escape(chars))))
+  }
+
+  /// Matches one character that is not in `chars` followed by the end of the line, so the
+  /// match cannot end with any of `chars`. As with `ends_with`, the anchor is matched where it
+  /// is added.
+  ///
+  /// Unlike a negative lookbehind such as `(?<!~)$`, this consumes the last character, so the
+  /// preceding combinators must leave at least one character for it. Lookarounds need the
+  /// `fancy` backend; see `as_fancy_regex`.
+  pub fn not_ending_with(self, chars: &str) -> Verex {
+    self.record(Step::NotEndingWith(chars.to_string()), |v| {
+      v.atom(&format!("(?:[^{}])", regex::escape(chars))).atom("$")
+    })
+  }
+
+  pub fn padded<F>(self, f: F) -> Verex where F: FnOnce(Verex) -> Verex {
+    let inner = f(self.scope());
+    let step = Step::Padded(inner.steps.clone());
+
+    let mut tokens = vec![Token::Atom("(?:\\s*)".to_string())];
+    tokens.extend(inner.into_tokens());
+    tokens.push(Token::Atom("(?:\\s*)".to_string()));
+    self.record(step, |v| v.push(Token::Group(tokens)))
+  }
+
+  /// The string form of `set_flag`. Each letter must name a `Flag`; anything else, such as a
+  /// JavaScript-style `"g"`, is rejected when the pattern is compiled.
+  pub fn add_modifier(self, modifier: &str) -> Verex {
+    self.record(Step::AddModifier(modifier.to_string()), |v| v.add(&format!("(?{})", modifier)))
+  }
+
+  pub fn remove_modifier(self, modifier: &str) -> Verex {
+    self.record(Step::RemoveModifier(modifier.to_string()), |v| v.add(&format!("(?-{})", modifier)))
+  }
+
+  /// Turns `flag` on from this point of the pattern onwards. `Flag::MultiLine` applies to the
+  /// whole pattern instead, like `search_multiline`, so that the anchors added by
+  /// `start_of_line` and `end_of_line` follow it too.
+  pub fn set_flag(self, flag: Flag) -> Verex {
+    self.record(Step::SetFlag(flag), |mut v| match flag {
+      Flag::MultiLine => {
+        v.multiline = true;
+        v
+      },
+      _ => v.add(&format!("(?{})", flag.letter())),
+    })
+  }
+
+  /// Turns `flag` off from this point of the pattern onwards. `Flag::MultiLine` applies to the
+  /// whole pattern, like `search_oneline`.
+  pub fn clear_flag(self, flag: Flag) -> Verex {
+    self.record(Step::ClearFlag(flag), |mut v| match flag {
+      Flag::MultiLine => {
+        v.multiline = false;
+        v
+      },
+      _ => v.add(&format!("(?-{})", flag.letter())),
+    })
+  }
+
+  pub fn with_any_case(self) -> Verex {
+    self.record(Step::WithAnyCase, |v| v.add_modifier("i"))
+  }
+
+  pub fn with_specific_case(self) -> Verex {
+    self.record(Step::WithSpecificCase, |v| v.remove_modifier("i"))
+  }
+
+  /// Makes `start_of_line` and `end_of_line` match at the start and end of every line rather than
+  /// only of the whole text. This applies to the whole pattern, wherever it is called in the chain.
+  pub fn search_multiline(self) -> Verex {
+    self.record(Step::SearchMultiline, |mut v| {
+      v.multiline = true;
+      v
+    })
+  }
+
+  pub fn search_oneline(self) -> Verex {
+    self.record(Step::SearchOneline, |mut v| {
+      v.multiline = false;
+      v
+    })
+  }
+
+  /// Matches `value` repeated as specified by `rep`.
+  pub fn repeated(self, value: &str, rep: Rep) -> Verex {
+    self.record(Step::Repeated(value.to_string(), rep), |v| {
+      if value.is_empty() { v } else { v.then(value).quantify(&rep.quantifier()) }
+    })
+  }
+
+  /// Matches `value` one or more times with no `counts`, exactly `counts[0]` times with one, and
+  /// from `counts[0]` to `counts[1]` times with two or more.
+  #[deprecated(note = "use `repeated` with a `Rep`, such as `repeated(value, Rep::Between(1, 3))`")]
+  pub fn multiple(self, value: &str, counts: &[i32]) -> Verex {
+    self.record(Step::Multiple(value.to_string(), counts.to_vec()), |v| match Rep::from_counts(counts) {
+      Some(rep) => v.repeated(value, rep),
+      // Negative counts have no `Rep`; they still give a quantifier the regex crate rejects.
+      None if value.is_empty() => v,
+      None if counts.len() == 1 => v.then(value).count(counts[0]),
+      None => v.then(value).count_range(counts[0], counts[1]),
+    })
+  }
+
+  pub fn one_or_more(self) -> Verex {
+    self.record(Step::OneOrMore, |v| {
+      let quantifier = v.unbounded_quantifier("+");
+      v.quantify(&quantifier)
+    })
+  }
+
+  pub fn zero_or_more(self) -> Verex {
+    self.record(Step::ZeroOrMore, |v| {
+      let quantifier = v.unbounded_quantifier("*");
+      v.quantify(&quantifier)
+    })
+  }
+
+  /// `quantifier`, which is `*` or `+`, or the bounded quantifier it becomes under
+  /// `Unbounded::Cap`.
+  fn unbounded_quantifier(&self, quantifier: &str) -> String {
+    match self.unbounded {
+      Unbounded::Cap(max) => {
+        let min = if quantifier == "+" { 1 } else { 0 };
+        format!("{{{},{}}}", min, max.max(min))
+      },
+      _ => quantifier.to_string(),
+    }
+  }
+
+  pub fn count(self, count: i32) -> Verex {
+    self.record(Step::Count(count), |v| v.quantify(&format!("{{{}}}", count)))
+  }
+
+  pub fn count_range(self, from: i32, to: i32) -> Verex {
+    self.record(Step::CountRange(from, to), |v| v.quantify(&format!("{{{},{}}}", from, to)))
+  }
+
+  pub fn at_least(self, from: i32) -> Verex {
+    self.record(Step::AtLeast(from), |v| v.quantify(&format!("{{{},}}", from)))
+  }
+
+  /// Like `count_range`, but matches as few repetitions as possible.
+  pub fn count_range_lazy(self, from: i32, to: i32) -> Verex {
+    self.record(Step::CountRangeLazy(from, to), |v| v.count_range(from, to).lazy())
+  }
+
+  /// Like `at_least`, but matches as few repetitions as possible.
+  pub fn at_least_lazy(self, from: i32) -> Verex {
+    self.record(Step::AtLeastLazy(from), |v| v.at_least(from).lazy())
+  }
+
+  /// Makes the quantifier added by the previous call, including the brace forms such as
+  /// `{2,5}`, match as few repetitions as possible. Has no effect if the previous call did not
+  /// add a quantifier or it is already lazy.
+  pub fn lazy(self) -> Verex {
+    self.record(Step::Lazy, |mut v| {
+      if let Some(&mut Token::Quantifier(ref mut quantifier)) = v.tokens.last_mut() {
+        if !quantifier.ends_with("??") && (quantifier == "?" || !quantifier.ends_with('?')) {
+          quantifier.push('?');
+          v.origins.truncate(v.tokens.len() - 1);
+        }
+      }
+      v
+    })
+  }
+
+  /// Makes the quantifier added by the previous call possessive, so that it never gives back
+  /// what it matched, like `*+` in other engines. Has no effect if the previous call did not
+  /// add a quantifier or it is lazy. Possessive quantifiers need the `fancy` backend;
+  /// `try_as_regex` reports `VerexError::RequiresFancyBackend`.
+  pub fn possessive(self) -> Verex {
+    self.record(Step::Possessive, |mut v| {
+      match v.tokens.pop() {
+        Some(Token::Quantifier(quantifier)) if quantifier == "?" || !quantifier.ends_with('?') => {
+          v.origins.truncate(v.tokens.len());
+          v.tokens.push(Token::Fancy { pattern: quantifier + "+", fallback: None });
+        },
+        Some(token) => v.tokens.push(token),
+        None => {},
+      }
+      v
+    })
+  }
+
+  pub fn or(self, value: &str) -> Self {
+    self.record(Step::Or(value.to_string()), |mut v| {
+      let p = if v.prefix.contains("(") { "" } else { "(" };
+      let s = if v.suffix.contains(")") { "" } else { ")" };
+      v.prefix.push_str(p);
+      v.suffix.push_str(s);
+      v.add(")|(").then(value)
+    })
+  }
+
+  pub fn begin_capture(self) -> Self {
+    self.record(Step::BeginCapture, |mut v| {
+      v.open_groups += 1;
+      v.suffix.push(')');
+      v.add("(")
+    })
+  }
+
+  pub fn begin_named_capture(self, name: &str) -> Self {
+    self.record(Step::BeginNamedCapture(name.to_string()), |mut v| {
+      v.open_groups += 1;
+      v.suffix.push(')');
+      v.add(&format!("(?P<{}>", name))
+    })
+  }
+
+  /// Begins an unnamed capture group that can later be located with `tag_to_index`, however
+  /// the expression is composed.
+  pub fn begin_capture_tagged(self, tag: &str) -> Self {
+    self.record(Step::BeginCaptureTagged(tag.to_string()), |mut v| {
+      v.open_groups += 1;
+      v.suffix.push(')');
+      v.push(Token::Tagged(tag.to_string()))
+    })
+  }
+
+  pub fn end_capture(self) -> Verex {
+    self.record(Step::EndCapture, |mut v| {
+      v.open_groups = v.open_groups.saturating_sub(1);
+      if let Some(i) = v.suffix.rfind(')') {
+        v.suffix.remove(i);
+      }
+      v.add(")")
+    })
+  }
+
+  /// Captures whatever `f` adds. Groups that `f` leaves open are closed along with this one.
+  pub fn capture<F>(self, f: F) -> Verex where F: FnOnce(Verex) -> Verex {
+    let inner = f(self.scope());
+    let step = Step::Capture(inner.steps.clone());
+
+    let mut tokens = vec![Token::Raw("(".to_string())];
+    tokens.extend(inner.into_tokens());
+    tokens.push(Token::Raw(")".to_string()));
+    self.record(step, |v| v.push(Token::Group(tokens)))
+  }
+
+  /// Requires the fragment built in `f` to match somewhere between this point and the end of the
+  /// line, without consuming any text. This is a lookahead, so the pattern needs the `fancy`
+  /// backend; `try_as_regex` reports `VerexError::RequiresFancyBackend`.
+  pub fn require_somewhere<F>(self, f: F) -> Verex where F: FnOnce(Verex) -> Verex {
+    let inner = f(self.scope());
+    let step = Step::RequireSomewhere(inner.steps.clone());
+    let pattern = format!("(?=.*?{})", render_for(&[Token::Group(inner.into_tokens())], Backend::Fancy));
+    self.record(step, |v| v.push(Token::Fancy { pattern, fallback: None }))
+  }
+
+  /// Matches the fragment built in `f` as an atomic group, which never gives back what it
+  /// matched to let the rest of the pattern match. This cuts off the backtracking that makes
+  /// nested quantifiers such as `(a+)+` slow on text that does not match. Atomic groups need
+  /// the `fancy` backend; `try_as_regex` reports `VerexError::RequiresFancyBackend`.
+  pub fn atomic<F>(self, f: F) -> Verex where F: FnOnce(Verex) -> Verex {
+    let inner = f(self.scope());
+    let step = Step::Atomic(inner.steps.clone());
+    let pattern = format!("(?>{})", render_for(&inner.into_tokens(), Backend::Fancy));
+    self.record(step, |v| v.push(Token::Fancy { pattern, fallback: None }))
+  }
+
+  /// Builds a fragment in `f` and repeats it as a whole, as specified by `rep`.
+  pub fn repeat_fragment<F>(self, f: F, rep: Rep) -> Verex where F: FnOnce(Verex) -> Verex {
+    let inner = f(self.scope());
+    let step = Step::RepeatFragment(inner.steps.clone(), rep);
+    self.record(step, |v| v.push(Token::Group(inner.into_tokens())).quantify(&rep.quantifier()))
+  }
+
+  /// Matches `min` to `max` characters of the one character class built in `f`, such as `digit`
+  /// or `any_of`, so that a token is limited in length as a whole. A quantifier in `f`, as in
+  /// `word`, is replaced. Anything but a single class is reported by `try_as_regex` as
+  /// `VerexError::NotACharacterClass`; `with_total_length` limits other fragments.
+  pub fn length_bounded<F>(self, f: F, min: u32, max: u32) -> Verex where F: FnOnce(Verex) -> Verex {
+    let inner = f(self.scope());
+    let step = Step::LengthBounded(inner.steps.clone(), min, max);
+    self.record(step, |v| match single_class(&inner.as_string()) {
+      Some(class) => v.atom(&format!("(?:{}){{{},{}}}", class, min, max)),
+      None => v.atom(NO_MATCH),
+    })
+  }
+
+  /// Appends `fragment`, built separately, as a non-capturing group. Its capture groups are
+  /// numbered after those before it; `composition_map` tells where they went.
+  pub fn concat(self, fragment: VerexFragment) -> Verex {
+    self.record(Step::Concat(fragment.steps.clone()), |mut v| {
+      let groups = capture_groups(&fragment.clone().as_string()).len();
+      v.renumber_captures(&[(0, groups)]);
+      v.push(Token::Group(fragment.into_tokens()))
+    })
+  }
+
+  /// Records where the groups of fragments about to be appended go, given as the number of
+  /// groups before each fragment's first within what is appended, and its number of groups.
+  pub(crate) fn renumber_captures(&mut self, fragments: &[(usize, usize)]) {
+    let before = capture_groups(&self.clone().as_string()).len();
+    self.composition.fragments.extend(fragments.iter().map(|&(offset, count)| (before + offset, count)));
+  }
+
+  /// Where the capture groups of the fragments joined with `concat` and `alternation` are in
+  /// the composed pattern. Only fragments composed at the top level are listed; those composed
+  /// inside a fragment are numbered within it.
+  pub fn composition_map(&self) -> &CompositionMap {
+    &self.composition
+  }
+
+  /// Names the fragment built in `f` for `insert`, without adding anything to the pattern.
+  /// Definitions are looked up when they are inserted, so they may refer to each other in any
+  /// order, and defining a name again replaces it for later inserts.
+  pub fn define<F>(mut self, name: &str, f: F) -> Verex where F: FnOnce(Verex) -> Verex {
+    let inner = f(self.scope());
+    self.steps.push(Step::Define(name.to_string(), inner.steps.clone()));
+    self.definitions.insert(name.to_string(), inner.steps);
+    self
+  }
+
+  /// Appends the fragment defined as `name` as a non-capturing group. Inserts work in fragments
+  /// built in closures, such as those of `capture`, but not in fragments built separately.
+  ///
+  /// An unknown name, or a definition that inserts itself through other definitions, is
+  /// reported by `try_as_regex` as `VerexError::UnknownDefinition` or
+  /// `VerexError::DefinitionCycle`; until then the insert matches nothing.
+  pub fn insert(self, name: &str) -> Verex {
+    let steps = if self.expanding.iter().any(|expanding| expanding == name) { None } else { self.definitions.get(name).cloned() };
+    self.record(Step::Insert(name.to_string()), |v| match steps {
+      Some(steps) => {
+        let mut inner = v.scope();
+        inner.expanding.push(name.to_string());
+        let inner = steps.iter().fold(inner, |inner, step| step.apply(inner));
+        v.push(Token::Group(inner.into_tokens()))
+      },
+      None => v.atom(NO_MATCH),
+    })
+  }
+
+  /// Matches a record of `fields` separated by `sep`, such as a line of pipe-separated values.
+  /// Every field is captured: a field with named groups of its own is kept as it is, and any
+  /// other is wrapped in a numbered group. With `allow_quoted`, each field may instead be a
+  /// `quoted_string`, which can contain the separator; the quotes are part of the capture.
+  pub fn delimited_record(self, fields: &[VerexFragment], sep: &str, allow_quoted: bool) -> Verex {
+    let steps = fields.iter().map(|field| field.steps.clone()).collect();
+    self.record(Step::DelimitedRecord(steps, sep.to_string(), allow_quoted), |v| v.delimited_fields(fields, sep, allow_quoted))
+  }
+
+  /// Appends the fields of `delimited_record`.
+  fn delimited_fields(mut self, fields: &[VerexFragment], sep: &str, allow_quoted: bool) -> Verex {
+    for (i, field) in fields.iter().enumerate() {
+      if i > 0 {
+        self = self.atom(&format!("(?:{})", regex::escape(sep)));
+      }
+      let named = field.try_as_regex().map(|regex| regex.capture_names().any(|name| name.is_some())).unwrap_or(false);
+      let mut tokens = Vec::new();
+      if !named {
+        tokens.push(Token::Raw("(".to_string()));
+      }
+      if allow_quoted {
+        tokens.push(Token::Atom(QUOTED_STRING.to_string()));
+        tokens.push(Token::Raw("|".to_string()));
+      }
+      tokens.push(Token::Group(field.clone().into_tokens()));
+      if !named {
+        tokens.push(Token::Raw(")".to_string()));
+      }
+      self = self.push(Token::Group(tokens));
+    }
+    self
+  }
+
+  /// Matches a block of lines from a line matching `start` to the next line matching `end`,
+  /// such as a PEM certificate between its `BEGIN` and `END` lines, and captures the lines in
+  /// between as `block`, without the line break before `end`. The markers must take up whole
+  /// lines, and the block may span any number of lines; a block without an end marker is not
+  /// matched. The block is as short as it can be, so a nested block ends at the first end
+  /// marker after `start`, and repeated blocks are matched one by one. If the pattern already
+  /// has a `block` group, the block is captured as `block_2`, `block_3` and so on instead.
+  pub fn between_lines(self, start: VerexFragment, end: VerexFragment) -> Verex {
+    self.record(Step::BetweenLines(start.steps.clone(), end.steps.clone()), |v| v.block_between(start, end))
+  }
+
+  /// Appends the block of `between_lines`.
+  fn block_between(self, start: VerexFragment, end: VerexFragment) -> Verex {
+    let rendered = [render(&self.tokens), render(&start.tokens), render(&end.tokens)];
+    let taken: HashSet<&str> = rendered.iter().flat_map(|pattern| group_names(pattern)).map(|(_, name)| name).collect();
+    let name = (1..).map(|n| if n == 1 { "block".to_string() } else { format!("block_{}", n) })
+      .find(|candidate| !taken.contains(&candidate[..])).expect("some suffix is free");
+    let block = format!(r"\r?\n(?:(?P<{}>(?s:.{}?))\r?\n)?", name, self.unbounded_quantifier("*"));
+    self.push(Token::Group(vec![
+      Token::Atom("(?m:^)".to_string()),
+      Token::Group(start.into_tokens()),
+      Token::Atom(block),
+      Token::Group(end.into_tokens()),
+      Token::Atom(r"\r?(?m:$)".to_string()),
+    ]))
+  }
+
+  /// Repeats everything built so far as a whole, as specified by `rep`. Flags and `^` and `$`
+  /// anchors apply to the repetition rather than being repeated, and while a group is open only
+  /// what was added since it began is repeated.
+  pub fn times(self, rep: Rep) -> Verex {
+    self.record(Step::Times(rep), |mut v| {
+      let body = v.take_body();
+      v.push(Token::Group(body)).quantify(&rep.quantifier())
+    })
+  }
+
+  /// Requires the match of everything built so far to be `min` to `max` characters long, which
+  /// needs the `fancy` backend. The pattern is matched in a lookahead that captures the rest of
+  /// the text, in a group named `__verex_length` and a number, and `min` to `max` characters
+  /// are then consumed up to where that rest begins. `fancy-regex` does not make positive
+  /// lookaheads atomic, so if the first match of the pattern has the wrong length its other
+  /// matches at the same position are tried, in the order the pattern's own quantifiers and
+  /// alternations give. Flags, anchors and open groups are kept outside, as with `times`.
+  pub fn with_total_length(self, min: u32, max: u32) -> Verex {
+    self.record(Step::WithTotalLength(min, max), |mut v| {
+      let body = v.take_body();
+      let inner = render_for(&body, Backend::Fancy);
+      v.length_checked(&inner, min, max)
+    })
+  }
+
+  /// Appends `pattern`, requiring its match to be `min` to `max` characters long in the way
+  /// `with_total_length` describes, which needs the `fancy` backend.
+  pub(crate) fn length_checked(self, pattern: &str, min: u32, max: u32) -> Verex {
+    let marker = format!("(?P<{}", LENGTH_MARKER);
+    let name = format!("{}{}", LENGTH_MARKER, render_for(&self.tokens, Backend::Fancy).matches(&marker).count() + pattern.matches(&marker).count());
+    let pattern = format!(r"(?=(?:{0})(?P<{1}>(?s:.*)))(?s:.){{{2},{3}}}(?=\k<{1}>\z)", pattern, name, min, max);
+    self.push(Token::Fancy { pattern, fallback: None })
+  }
+
+  /// The number of groups opened with `begin_capture` or `begin_named_capture` that have not
+  /// been closed yet.
+  pub fn open_group_count(&self) -> usize {
+    self.open_groups
+  }
+
+  /// Whether `compile` closes groups that are still open instead of failing with
+  /// `VerexError::UnclosedGroups`.
+  pub fn auto_close_groups(self, enabled: bool) -> Verex {
+    self.record(Step::AutoCloseGroups(enabled), |mut v| {
+      v.auto_close_groups = enabled;
+      v
+    })
+  }
+
+  /// With `enabled`, spaces in the values of later `then` and `find` calls match any run of
+  /// whitespace, so "hello world" also matches "hello   world" and "hello\nworld". Runs of
+  /// spaces in a value are treated as one.
+  pub fn tolerant_whitespace(self, enabled: bool) -> Verex {
+    self.record(Step::TolerantWhitespace(enabled), |mut v| {
+      v.tolerant_whitespace = enabled;
+      v
+    })
+  }
+
+  /// Sets how capture groups that share a name are resolved when the pattern is rendered. See
+  /// `DuplicateGroups`; `capture_names` lists the names as resolved.
+  pub fn duplicate_groups(self, policy: DuplicateGroups) -> Verex {
+    self.record(Step::DuplicateGroups(policy), |mut v| {
+      v.duplicate_groups = policy;
+      v
+    })
+  }
+
+  /// Sets what later combinators that match runs of any length, such as `anything` and
+  /// `one_or_more`, do, so that untrusted input cannot make a match or its captures grow
+  /// without limit. With `Unbounded::Forbid` they are reported when the pattern is compiled,
+  /// including in fragments joined with `concat` or `alternation`; with `Unbounded::Cap` they
+  /// match a bounded number of repetitions, including in fragments built in closures.
+  /// `anything_up_to` and `something_up_to` are bounded alternatives; `raw` patterns are not
+  /// checked.
+  pub fn unbounded(self, policy: Unbounded) -> Verex {
+    self.record(Step::Unbounded(policy), |mut v| {
+      v.unbounded = policy;
+      v
+    })
+  }
+
+  /// `unbounded(Unbounded::Forbid)` if `forbid` is set, and `unbounded(Unbounded::Allow)`
+  /// otherwise.
+  pub fn forbid_unbounded(self, forbid: bool) -> Verex {
+    self.unbounded(if forbid { Unbounded::Forbid } else { Unbounded::Allow })
+  }
+
+  /// Requires the whole text to match the pattern, ignoring leading and trailing whitespace.
+  /// `start_of_line` and `end_of_line` anchors are dropped, since the whole text is anchored.
+  /// Flags stay in effect, and while a group is open only what was added since it began is
+  /// trimmed, as with `times`.
+  pub fn whitespace_trimmed(self) -> Verex {
+    self.record(Step::WhitespaceTrimmed, |mut v| {
+      v.prefix = v.prefix.replace('^', "");
+      v.suffix = v.suffix.replace('$', "");
+      let body = v.take_body();
+      v.atom("(?:\\A\\s*)").push(Token::Group(body)).atom("(?:\\s*\\z)")
+    })
+  }
+
+  pub fn validate_trimmed(&self, text: &str) -> bool {
+    self.clone().whitespace_trimmed().is_match(text)
+  }
+
+  /// Matches against `text` with its leading and trailing whitespace removed and every other
+  /// run of whitespace collapsed to a single space.
+  pub fn matches_normalized(&self, text: &str) -> bool {
+    let normalized: Vec<&str> = text.split_whitespace().collect();
+    self.clone().is_match(&normalized.join(" "))
+  }
+
+  pub fn is_match(self, text: &str) -> bool {
+    match self.as_literal() {
+      Some(literal) => text.contains(&literal[..]),
+      None => self.as_regex().is_match(text),
+    }
+  }
+
+  pub fn captures(self, text: &str) -> Vec<String> {
+    match self.as_regex().captures(text) {
+      Some(captures) => captures.iter().map(|x| x.expect("captures method failed").as_str().to_string()).collect(),
+      None => Vec::new(),
+    }
+  }
+
+  pub fn split(self, text: &str) -> Vec<String> {
+    self.as_regex().split(text).map(|x| x.to_string()).collect()
+  }
+
+  /// Compiles the pattern for this call only. To replace in many strings, or to get the text
+  /// back borrowed when nothing matches, use `CompiledVerex::replace_cow`.
+  pub fn replace(self, text: &str, rep: &str) -> String {
+    self.as_regex().replace(text, rep).into_owned()
+  }
+
+  /// Like `replace`, but for every match. `CompiledVerex::replace_all_cow` is the borrowing form.
+  pub fn replace_all(self, text: &str, rep: &str) -> String {
+    self.as_regex().replace_all(text, rep).into_owned()
+  }
+
+  /// Matches the tokens one at a time against the start of `text` and reports the first one
+  /// that fails. Every token is matched greedily and never revisited, so the diagnosis may be
+  /// inexact for patterns that rely on alternation or backtracking across tokens.
+  pub fn diagnose(&self, text: &str) -> MatchDiagnosis {
+    let mut offset = 0;
+    let mut flags = String::new();
+    let mut i = 0;
+    while i < self.tokens.len() {
+      let mut end = i + 1;
+      while let Some(&Token::Quantifier(_)) = self.tokens.get(end) {
+        end += 1;
+      }
+      let unit = &self.tokens[i..end];
+      match unit[0] {
+        Token::Raw(ref raw) if raw.starts_with("(?") => flags.push_str(raw),
+        Token::Raw(_) | Token::Tagged(_) | Token::Quantifier(_) => {},
+        Token::Literal(_) | Token::Atom(_) | Token::Group(_) | Token::Fancy { .. } | Token::Bytes(_) => {
+          match anchored_match(&flags, &render(unit), &text[offset..]) {
+            Some(len) => offset += len,
+            None => {
+              let atom = render(&unit[..1]);
+              while let Some(len) = anchored_match(&flags, &atom, &text[offset..]) {
+                if len == 0 {
+                  break;
+                }
+                offset += len;
+              }
+              return MatchDiagnosis { failed_token: Some(i), description: Some(render(unit)), offset };
+            },
+          }
+        },
+      }
+      i = end;
+    }
+
+    if self.suffix.contains('$') && offset != text.len() {
+      return MatchDiagnosis { failed_token: Some(self.tokens.len()), description: Some("$".to_string()), offset };
+    }
+    MatchDiagnosis { failed_token: None, description: None, offset }
+  }
+
+  /// Describes why `text` does not match, for use in test failure messages: the pattern, the
+  /// input with a caret under the offset where `diagnose` found the first divergence, and what was
+  /// expected there. The caret is aligned by characters, so wide characters earlier in the input
+  /// may shift it in some terminals.
+  pub fn explain_mismatch(&self, text: &str) -> String {
+    let pattern = self.clone().as_string();
+    if self.clone().is_match(text) {
+      return format!("pattern `{}` matches input {:?}", pattern, text);
+    }
+    let diagnosis = self.diagnose(text);
+    let column = format!("{:?}", &text[..diagnosis.offset]).chars().count() - 1;
+    let found = match text[diagnosis.offset..].chars().next() {
+      Some(c) => format!("{:?}", c),
+      None => "end of input".to_string(),
+    };
+    let divergence = match diagnosis.description {
+      Some(ref expected) => format!("first divergence at offset {} (expected `{}`, found {})", diagnosis.offset, expected, found),
+      None => format!("no divergence found before offset {}, found {}", diagnosis.offset, found),
+    };
+    format!("pattern: `{}`\ninput:   {:?}\n{:width$}^\n{}", pattern, text, "", divergence, width = 9 + column)
+  }
+
+  /// The index of the capture group named `name` in the composed pattern. The pattern is read
+  /// for its groups, not compiled, so asking for several names stays cheap.
+  pub fn group_index_of(&self, name: &str) -> Option<usize> {
+    validate_steps(&self.steps, None).ok()?;
+    let pattern = self.clone().as_string();
+    let groups = capture_groups(&pattern);
+    groups.iter().position(|group| group.as_ref().map(|&(_, n)| n) == Some(name)).map(|i| i + 1)
+  }
+
+  /// The index of the first capture group begun with `begin_capture_tagged(tag)` in the composed
+  /// pattern.
+  pub fn tag_to_index(&self, tag: &str) -> Option<usize> {
+    let mut marked = self.clone();
+    if !mark_tag(&mut marked.tokens, tag) {
+      return None;
+    }
+    marked.group_index_of(TAG_MARKER)
+  }
+
+  /// A hash of the pattern, for use as a cache key. It is computed over the tokens of the
+  /// normalized steps, as `VerexBuilder::build` would apply them, so patterns that differ only in
+  /// redundant mode switches or in which equivalent method produced a token share a fingerprint.
+  /// The value is stable across platforms and within a major version of this crate.
+  pub fn fingerprint(&self) -> u64 {
+    let normalized = Verex::from_steps(&VerexBuilder::from_verex(self).normalized());
+    let mut fnv = Fnv::new();
+    fnv.tokens(&normalized.into_tokens());
+    fnv.0
+  }
+
+  /// The pattern of `as_string` across several lines: one per literal, class or group, indented
+  /// by group, with a comment naming the combinator that added it where that can be told. The
+  /// output is the same from one run to the next, and with the `x` flag it compiles to a regex
+  /// that matches what `as_string` does, for which whitespace and `#` are escaped.
+  pub fn to_pretty_string(&self) -> String {
+    let mut pretty = Pretty { out: String::new(), depth: 0 };
+    for raw in [self.flags(), &self.prefix] {
+      if !raw.is_empty() {
+        pretty.line(raw, &[]);
+      }
+    }
+    pretty.tokens(&self.tokens, &token_labels(&self.steps, &self.origins));
+    if !self.suffix.is_empty() {
+      pretty.line(&self.suffix, &[]);
+    }
+    self.resolve_group_names(pretty.out)
+  }
+
+  pub fn as_string(self) -> String {
+    let pattern = self.flags().to_string() + &self.prefix + &render(&self.tokens) + &self.suffix;
+    self.resolve_group_names(pattern)
+  }
+
+  fn resolve_group_names(&self, pattern: String) -> String {
+    match self.duplicate_groups {
+      DuplicateGroups::Reject => pattern,
+      DuplicateGroups::Suffix => suffix_duplicate_groups(&pattern),
+    }
+  }
+
+  /// Resolves the numbering of the capture groups, as `VerexBuilder::build` does before it
+  /// returns the expression: a group name used twice is suffixed or reported as
+  /// `duplicate_groups` says, and a tag given to a second group, which `tag_to_index` would
+  /// never find, is reported as `VerexError::DuplicateTag`.
+  pub(crate) fn check_groups(&self) -> Result<(), VerexError> {
+    self.check_group_names(&self.clone().as_string())?;
+    match duplicate_tag(&self.tokens) {
+      Some(tag) => {
+        let step = (1..=self.steps.len())
+          .find(|&n| duplicate_tag(&Verex::from_steps(&self.steps[..n]).tokens).is_some())
+          .map_or(self.steps.len().saturating_sub(1), |n| n - 1);
+        Err(VerexError::DuplicateTag { tag, step })
+      },
+      None => Ok(()),
+    }
+  }
+
+  /// Reports a group name that `pattern` uses twice, at the first step after which the
+  /// pattern built so far does.
+  fn check_group_names(&self, pattern: &str) -> Result<(), VerexError> {
+    match duplicate_group_name(pattern) {
+      Some(name) => {
+        let step = (1..=self.steps.len())
+          .find(|&n| duplicate_group_name(&Verex::from_steps(&self.steps[..n]).as_string()).is_some())
+          .map_or(self.steps.len().saturating_sub(1), |n| n - 1);
+        Err(VerexError::DuplicateGroupName { name, step })
+      },
+      None => Ok(()),
+    }
+  }
+
+  /// The names of the capture groups of the composed pattern, by group index, after
+  /// `duplicate_groups` has resolved them. Group 0, the whole match, and unnamed groups have no
+  /// name.
+  pub fn capture_names(&self) -> Result<Vec<Option<String>>, VerexError> {
+    let regex = self.try_as_regex()?;
+    Ok(regex.capture_names().map(|name| name.map(str::to_string)).collect())
+  }
+
+  /// # Panics
+  ///
+  /// Panics with the `VerexError` message if the pattern is invalid.
+  pub fn as_regex(self) -> Regex {
+    self.try_as_regex().unwrap_or_else(|err| panic!("{}", err))
+  }
+
+  /// The first part of the pattern that the `regex` crate cannot compile as a `Regex`, because
+  /// it needs the `fancy` backend or matches non-ASCII bytes.
+  pub(crate) fn backend_construct(&self) -> Option<&str> {
+    backend_token(&self.tokens)
+  }
+
+  /// Whether the pattern has a lookaround, which the default backend either rejects or replaces
+  /// with a fallback that matches more.
+  pub(crate) fn has_lookaround(&self) -> bool {
+    has_fancy(&self.tokens)
+  }
+
+  /// The indices of the `anything` steps at the start and end of the steps that
+  /// `strip_redundant_dotstar` drops: those whose tokens are still the outermost ones, with no
+  /// anchor, flag or later quantifier rendered next to them.
+  pub(crate) fn redundant_dotstar(&self) -> Vec<usize> {
+    let steps = &self.steps;
+    let leading = steps.iter().take_while(|&step| *step == Step::Anything).count();
+    let policy = steps.iter().rev().find_map(|step| match *step {
+      Step::Unbounded(policy) => Some(policy),
+      _ => None,
+    }).unwrap_or_default();
+    let trailing = if policy == Unbounded::Allow { steps[leading..].iter().rev().take_while(|&step| *step == Step::Anything).count() } else { 0 };
+    if leading + trailing == 0 {
+      return Vec::new();
+    }
+    let dotstar = Verex::new().anything().tokens;
+    let tokens = self.clone().into_tokens();
+    let kept: Vec<Step> = steps[leading..steps.len() - trailing].to_vec();
+    let stripped = Verex::from_steps(&kept).into_tokens();
+    if tokens.len() != stripped.len() + leading + trailing {
+      return Vec::new();
+    }
+    let (head, rest) = tokens.split_at(leading);
+    let (middle, tail) = rest.split_at(stripped.len());
+    let outermost = head.iter().chain(tail).all(|token| dotstar.contains(token));
+    let quantified = match middle.first() {
+      Some(&Token::Quantifier(_)) => leading > 0,
+      _ => false,
+    };
+    if !outermost || quantified || middle != &stripped[..] {
+      return Vec::new();
+    }
+    (0..leading).chain(steps.len() - trailing..steps.len()).collect()
+  }
+
+  pub fn try_as_regex(&self) -> Result<Regex, VerexError> {
+    validate_steps(&self.steps, None)?;
+    if requires_fancy(&self.tokens) {
+      return Err(VerexError::RequiresFancyBackend);
+    }
+    if requires_bytes(&self.tokens) {
+      return Err(VerexError::RequiresBytesBackend);
+    }
+    let pattern = self.clone().as_string();
+    self.check_group_names(&pattern)?;
+    Regex::new(&pattern).map_err(|error| VerexError::Compile { pattern, error })
+  }
+
+  /// Compiles the pattern for matching `&[u8]` haystacks, such as text in a legacy encoding. Byte
+  /// classes such as `non_ascii_byte` are only accepted here.
+  pub fn try_as_bytes_regex(&self) -> Result<regex::bytes::Regex, VerexError> {
+    validate_steps(&self.steps, None)?;
+    if requires_fancy(&self.tokens) {
+      return Err(VerexError::RequiresFancyBackend);
+    }
+    let pattern = self.clone().as_string();
+    self.check_group_names(&pattern)?;
+    regex::bytes::Regex::new(&pattern).map_err(|error| VerexError::Compile { pattern, error })
+  }
+
+  #[cfg(feature = "fancy")]
+  pub fn as_fancy_regex(&self) -> Result<fancy_regex::Regex, VerexError> {
+    validate_steps(&self.steps, None)?;
+    if requires_bytes(&self.tokens) {
+      return Err(VerexError::RequiresBytesBackend);
+    }
+    let pattern = self.flags().to_string() + &self.prefix + &render_for(&self.tokens, Backend::Fancy) + &self.suffix;
+    let pattern = self.resolve_group_names(pattern);
+    self.check_group_names(&pattern)?;
+    fancy_regex::Regex::new(&pattern).map_err(VerexError::FancyCompile)
+  }
+
+  pub fn as_literal(&self) -> Option<String> {
+    if !self.prefix.is_empty() || !self.suffix.is_empty() {
+      return None;
+    }
+    let mut literal = String::new();
+    for token in &self.tokens {
+      match *token {
+        Token::Literal(ref s) if regex::escape(s) == *s => literal.push_str(s),
+        _ => return None,
+      }
+    }
+    Some(literal)
+  }
+
+  pub fn is_literal(&self) -> bool {
+    self.as_literal().is_some()
+  }
+
+  pub(crate) fn check_closed(&self) -> Result<(), VerexError> {
+    if self.open_groups > 0 && !self.auto_close_groups {
+      return Err(VerexError::UnclosedGroups { count: self.open_groups });
+    }
+    Ok(())
+  }
+
+  /// With the `tracing` feature, compiling is wrapped in a `verex.compile` span that records
+  /// the pattern length, the number of groups and the time taken.
+  pub fn compile(&self) -> Result<CompiledVerex, VerexError> {
+    #[cfg(feature = "tracing")]
+    let span = instrument::CompileSpan::start();
+    self.check_closed()?;
+    let regex = self.try_as_regex()?;
+    #[cfg(feature = "tracing")]
+    span.finish(&regex);
+    let literal = self.as_literal().map(Arc::from);
+    Ok(CompiledVerex::new(regex, literal))
+  }
+
+  pub fn into_shared(self) -> Result<SharedVerex, VerexError> {
+    self.compile()
+  }
+
+  /// Compiles each of `patterns`, which are keyed by a rule name, and reports every one that
+  /// fails rather than only the first, in the order given. See `compile_partial` to keep the
+  /// rules that compiled as well.
+  pub fn compile_all(patterns: Vec<(String, Verex)>) -> Result<HashMap<String, CompiledVerex>, Vec<(String, VerexError)>> {
+    let (compiled, errors) = Verex::compile_partial(patterns);
+    if errors.is_empty() { Ok(compiled) } else { Err(errors) }
+  }
+
+  /// Like `compile_all`, but returns the rules that compiled along with the errors of those
+  /// that did not. A name given twice keeps the later rule.
+  pub fn compile_partial(patterns: Vec<(String, Verex)>) -> (HashMap<String, CompiledVerex>, Vec<(String, VerexError)>) {
+    let mut compiled = HashMap::new();
+    let mut errors = Vec::new();
+    for (name, verex) in patterns {
+      match verex.compile() {
+        Ok(regex) => {
+          compiled.insert(name, regex);
+        },
+        Err(err) => errors.push((name, err)),
+      }
+    }
+    (compiled, errors)
+  }
+}
+
+impl Default for Verex {
+  fn default() -> Verex {
+    Verex::new()
+  }
+}
+
+/// Hashes the `fingerprint`.
+impl Hash for Verex {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_u64(self.fingerprint());
+  }
+}
+
+/// A piece of a pattern built on its own, to be joined with others by `Verex::concat`, `collect`
+/// or `extend`.
+pub type VerexFragment = Verex;
+
+impl FromIterator<VerexFragment> for Verex {
+  fn from_iter<I: IntoIterator<Item = VerexFragment>>(fragments: I) -> Verex {
+    fragments.into_iter().fold(Verex::new(), Verex::concat)
+  }
+}
+
+impl Extend<VerexFragment> for Verex {
+  fn extend<I: IntoIterator<Item = VerexFragment>>(&mut self, fragments: I) {
+    let v = mem::take(self);
+    *self = fragments.into_iter().fold(v, Verex::concat);
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchDiagnosis {
+  pub failed_token: Option<usize>,
+  pub description: Option<String>,
+  pub offset: usize,
+}
+
+impl MatchDiagnosis {
+  pub fn is_match(&self) -> bool {
+    self.failed_token.is_none()
+  }
+}
@@ -0,0 +1,272 @@
+use regex;
+use regex_syntax;
+use regex_syntax::ast::{Ast, GroupKind};
+use regex_syntax::hir::HirKind;
+
+use builder::{Rep, Step, Verex, NO_MATCH};
+use error::VerexError;
+
+/// A bound accepted by `Verex::range`.
+pub trait RangeBound {
+  fn bound(&self) -> char;
+}
+
+impl RangeBound for char {
+  fn bound(&self) -> char {
+    *self
+  }
+}
+
+impl RangeBound for &str {
+  fn bound(&self) -> char {
+    let mut chars = self.chars();
+    match (chars.next(), chars.next()) {
+      (Some(c), None) => c,
+      _ => panic!("{}", VerexError::InvalidRangeBound(self.to_string())),
+    }
+  }
+}
+
+/// The one character class that `pattern` matches, repeated or not, such as `\w` for
+/// `(?:\w+)`, or `None` if it matches anything else.
+pub(crate) fn single_class(pattern: &str) -> Option<String> {
+  let parsed = regex_syntax::ast::parse::Parser::new().parse(pattern).ok()?;
+  let mut ast = &parsed;
+  loop {
+    ast = match *ast {
+      Ast::Group(ref group) => match group.kind {
+        GroupKind::NonCapturing(ref flags) if flags.items.is_empty() => &group.ast,
+        _ => return None,
+      },
+      Ast::Repetition(ref repetition) => &repetition.ast,
+      _ => break,
+    };
+  }
+  let span = ast.span();
+  let class = &pattern[span.start.offset..span.end.offset];
+  match *regex_syntax::parse(class).ok()?.kind() {
+    HirKind::Class(_) => Some(class.to_string()),
+    _ => None,
+  }
+}
+
+/// The bytes that Windows-1252 assigns a character to, other than control characters but tab and
+/// line breaks.
+const WINDOWS_1252: &[(u8, u8)] = &[(b'\t', b'\n'), (b'\r', b'\r'), (0x20, 0x7e), (0x80, 0x80), (0x82, 0x8c), (0x8e, 0x8e), (0x91, 0x9c), (0x9e, 0xff)];
+
+/// A class of the bytes in `ranges` with Unicode mode off, so that it matches single bytes, and
+/// whether any of them is outside ASCII.
+fn byte_class(ranges: &[(u8, u8)]) -> (String, bool) {
+  if ranges.is_empty() {
+    return (NO_MATCH.to_string(), false);
+  }
+  let body: String = ranges.iter().map(|&(from, to)| {
+    if from == to { format!(r"\x{:02X}", from) } else { format!(r"\x{:02X}-\x{:02X}", from, to) }
+  }).collect();
+  (format!("(?-u:[{}])", body), ranges.iter().any(|&(_, to)| to >= 0x80))
+}
+
+impl Verex {
+  /// Matches a byte from `from` to `to` inclusive. A range that reaches outside ASCII can only
+  /// be compiled with `try_as_bytes_regex`; `try_as_regex` reports
+  /// `VerexError::RequiresBytesBackend`. A range with `from` after `to` is reported as
+  /// `VerexError::EmptyClass`.
+  pub fn byte_range(self, from: u8, to: u8) -> Verex {
+    let ranges = if from <= to { vec![(from, to)] } else { Vec::new() };
+    self.record(Step::ByteRange(from, to), |v| {
+      let (pattern, non_ascii) = byte_class(&ranges);
+      v.bytes_atom(&pattern, non_ascii)
+    })
+  }
+
+  /// Matches a printable ASCII byte, from space to `~`. Unlike the other byte classes, it is
+  /// valid text as well, so `try_as_regex` accepts it.
+  pub fn ascii_printable(self) -> Verex {
+    self.record(Step::AsciiPrintable, |v| v.byte_range(0x20, 0x7e))
+  }
+
+  /// Matches a byte outside ASCII, such as a byte of a Latin-1 character or of a UTF-8 sequence.
+  /// Only `try_as_bytes_regex` accepts it.
+  pub fn non_ascii_byte(self) -> Verex {
+    self.record(Step::NonAsciiByte, |v| v.byte_range(0x80, 0xff))
+  }
+
+  /// Matches any one of `bytes`. Bytes outside ASCII can only be compiled with
+  /// `try_as_bytes_regex`, and no bytes is reported as `VerexError::EmptyClass`.
+  pub fn bytes_of(self, bytes: &[u8]) -> Verex {
+    let mut sorted = bytes.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let ranges: Vec<(u8, u8)> = sorted.into_iter().map(|byte| (byte, byte)).collect();
+    self.record(Step::BytesOf(bytes.to_vec()), |v| {
+      let (pattern, non_ascii) = byte_class(&ranges);
+      v.bytes_atom(&pattern, non_ascii)
+    })
+  }
+
+  /// Matches a byte of text encoded in Windows-1252: a printable character, a tab or a line
+  /// break, leaving out the five bytes the encoding does not assign and other control
+  /// characters. Only `try_as_bytes_regex` accepts it.
+  pub fn windows1252_bytes(self) -> Verex {
+    self.record(Step::Windows1252Bytes, |v| {
+      let (pattern, non_ascii) = byte_class(WINDOWS_1252);
+      v.bytes_atom(&pattern, non_ascii)
+    })
+  }
+
+  pub fn line_break(self) -> Verex {
+    self.record(Step::LineBreak, |v| v.atom("(?:(?:\\n)|(?:\\r\\n))"))
+  }
+
+  pub fn br(self) -> Verex {
+    self.record(Step::Br, |v| v.line_break())
+  }
+
+  pub fn tab(self) -> Verex {
+    self.record(Step::Tab, |v| v.atom("(?:\\t)"))
+  }
+
+  pub fn word(self) -> Verex {
+    self.record(Step::Word, |v| {
+      let class = v.class("[0-9A-Za-z_]+", "\\w+");
+      v.atom(&class)
+    })
+  }
+
+  pub fn non_word(self) -> Verex {
+    self.record(Step::NonWord, |v| {
+      let class = v.class("[^0-9A-Za-z_]+", "\\W+");
+      v.atom(&class)
+    })
+  }
+
+  /// Matches a decimal digit. This is any Unicode digit, such as `٣`, unless `ascii_only` is set.
+  pub fn digit(self) -> Verex {
+    self.record(Step::Digit, |v| {
+      let class = v.class("[0-9]", "\\d");
+      v.atom(&class)
+    })
+  }
+
+  pub fn non_digit(self) -> Verex {
+    self.record(Step::NonDigit, |v| {
+      let class = v.class("[^0-9]", "\\D");
+      v.atom(&class)
+    })
+  }
+
+  pub fn ascii_digit(self) -> Verex {
+    self.record(Step::AsciiDigit, |v| v.atom("(?:[0-9])"))
+  }
+
+  /// Matches any Unicode decimal digit, including those of other scripts such as `٣` or `३`.
+  pub fn unicode_digit(self) -> Verex {
+    self.record(Step::UnicodeDigit, |v| v.atom("(?:\\d)"))
+  }
+
+  pub fn space(self) -> Verex {
+    self.record(Step::Space, |v| {
+      let class = v.class("[\\t\\n\\x0B\\x0C\\r ]", "\\s");
+      v.atom(&class)
+    })
+  }
+
+  pub fn non_space(self) -> Verex {
+    self.record(Step::NonSpace, |v| {
+      let class = v.class("[^\\t\\n\\x0B\\x0C\\r ]", "\\S");
+      v.atom(&class)
+    })
+  }
+
+  /// Matches `rep` digits as a single token, so the repetition cannot bind to anything else.
+  /// `digit_n(Rep::Exactly(4))` is `(?:\d{4})`.
+  pub fn digit_n(self, rep: Rep) -> Verex {
+    self.record(Step::DigitN(rep), |v| {
+      let class = v.class_n("[0-9]", "\\d", rep);
+      v.atom(&class)
+    })
+  }
+
+  pub fn non_digit_n(self, rep: Rep) -> Verex {
+    self.record(Step::NonDigitN(rep), |v| {
+      let class = v.class_n("[^0-9]", "\\D", rep);
+      v.atom(&class)
+    })
+  }
+
+  pub fn space_n(self, rep: Rep) -> Verex {
+    self.record(Step::SpaceN(rep), |v| {
+      let class = v.class_n("[\\t\\n\\x0B\\x0C\\r ]", "\\s", rep);
+      v.atom(&class)
+    })
+  }
+
+  pub fn non_space_n(self, rep: Rep) -> Verex {
+    self.record(Step::NonSpaceN(rep), |v| {
+      let class = v.class_n("[^\\t\\n\\x0B\\x0C\\r ]", "\\S", rep);
+      v.atom(&class)
+    })
+  }
+
+  pub fn tab_n(self, rep: Rep) -> Verex {
+    self.record(Step::TabN(rep), |v| v.atom(&format!("(?:\\t{})", rep.quantifier())))
+  }
+
+  fn class_n(&self, ascii: &str, unicode: &str, rep: Rep) -> String {
+    format!("(?:{}{})", if self.ascii_only { ascii } else { unicode }, rep.quantifier())
+  }
+
+  pub fn optional_whitespace(self) -> Verex {
+    self.record(Step::OptionalWhitespace, |v| v.atom("(?:\\s*)"))
+  }
+
+  pub fn required_whitespace(self) -> Verex {
+    self.record(Step::RequiredWhitespace, |v| v.atom("(?:\\s+)"))
+  }
+
+  pub fn optional_horizontal_whitespace(self) -> Verex {
+    self.record(Step::OptionalHorizontalWhitespace, |v| v.atom("(?:[^\\S\\r\\n]*)"))
+  }
+
+  pub fn required_horizontal_whitespace(self) -> Verex {
+    self.record(Step::RequiredHorizontalWhitespace, |v| v.atom("(?:[^\\S\\r\\n]+)"))
+  }
+
+  pub fn any_of(self, value: &str) -> Verex {
+    self.record(Step::AnyOf(value.to_string()), |v| v.atom(&format!("[{}]", value)))
+  }
+
+  #[deprecated(note = "use `any_of`, which this has always been the same as")]
+  pub fn any(self, value: &str) -> Verex {
+    self.record(Step::Any(value.to_string()), |v| v.any_of(value))
+  }
+
+  /// Matches a character in any of the inclusive ranges, given as single-character strings or
+  /// `char`s.
+  ///
+  /// # Panics
+  ///
+  /// Panics if a bound is not exactly one character or a range is reversed.
+  pub fn range<B: RangeBound>(self, pairs: &[(B, B)]) -> Verex {
+    let bounds: Vec<(char, char)> = pairs.iter().map(|(from, to)| (from.bound(), to.bound())).collect();
+    let mut ranges = String::new();
+    for &(from, to) in &bounds {
+      if from > to {
+        panic!("{}", VerexError::InvalidRange { from, to });
+      }
+      ranges.push_str(&format!("{}-{}", regex::escape(&from.to_string()), regex::escape(&to.to_string())));
+    }
+
+    let step = Step::Range(bounds.iter().map(|&(from, to)| (from.to_string(), to.to_string())).collect());
+    self.record(step, |v| v.atom(&format!("[{}]", ranges)))
+  }
+
+  /// Makes the `digit`, `word` and `space` families added after this call match ASCII
+  /// characters only.
+  pub fn ascii_only(self) -> Verex {
+    self.record(Step::AsciiOnly, |mut v| {
+      v.ascii_only = true;
+      v
+    })
+  }
+}
@@ -0,0 +1,779 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter;
+use std::ops::{ControlFlow, Range};
+use std::sync::{Arc, OnceLock};
+use regex::{CaptureMatches, Captures, Match, Regex, RegexBuilder};
+#[cfg(feature = "dfa")]
+use regex_automata;
+#[cfg(feature = "serde")]
+use serde_json::{Map, Value};
+
+use builder::Flag;
+use error::VerexError;
+#[cfg(feature = "tracing")]
+use instrument;
+use iter::{highlights, split_outside_quotes, split_with_delimiters, tokenize, Highlight, Segment, SplitPart};
+
+pub(crate) fn named_groups(regex: &Regex, captures: &Captures) -> HashMap<String, Option<String>> {
+  regex.capture_names()
+    .flatten()
+    .map(|name| (name.to_string(), captures.name(name).map(|m| m.as_str().to_string())))
+    .collect()
+}
+
+/// The named groups of a match as a JSON object of strings, with `null` for groups that did not
+/// take part in the match.
+#[cfg(feature = "serde")]
+pub(crate) fn named_groups_json(regex: &Regex, captures: &Captures) -> Value {
+  let groups: Map<String, Value> = regex.capture_names()
+    .flatten()
+    .map(|name| (name.to_string(), captures.name(name).map_or(Value::Null, |m| Value::String(m.as_str().to_string()))))
+    .collect();
+  Value::Object(groups)
+}
+
+pub(crate) fn participating(groups: HashMap<String, Option<String>>) -> HashMap<String, String> {
+  groups.into_iter().filter_map(|(name, value)| value.map(|value| (name, value))).collect()
+}
+
+/// Whether `name` is a group captured by `Verex::between_lines`: `block`, or `block_` and a
+/// number.
+fn is_block_group(name: &str) -> bool {
+  name == "block" || name.strip_prefix("block_").is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+pub(crate) fn captures_tuple<T: CaptureTuple>(regex: &Regex, text: &str) -> Result<Option<T>, VerexError> {
+  let found = regex.captures_len() - 1;
+  if found != T::arity() {
+    return Err(VerexError::GroupCountMismatch { expected: T::arity(), found });
+  }
+  match regex.captures(text) {
+    Some(captures) => {
+      let groups: Vec<_> = captures.iter().skip(1).map(|x| x.map(|x| x.as_str())).collect();
+      T::from_groups(&groups).map(Some)
+    },
+    None => Ok(None),
+  }
+}
+
+/// Masks the letters and digits of each match, or of `group` within each match, keeping the rest
+/// of the text and any part of a match captured by a group named `keep`.
+pub(crate) fn mask(regex: &Regex, text: &str, mask_char: char, group: Option<usize>) -> String {
+  let keep = regex.capture_names().position(|name| name == Some("keep"));
+  let mut masked = String::with_capacity(text.len());
+  let mut last = 0;
+  for captures in regex.captures_iter(text) {
+    let span = match captures.get(group.unwrap_or(0)) {
+      Some(span) => span,
+      None => continue,
+    };
+    let kept = keep.and_then(|keep| captures.get(keep)).map(|kept| kept.range());
+    masked.push_str(&text[last..span.start()]);
+    for (offset, c) in span.as_str().char_indices() {
+      let is_kept = kept.as_ref().is_some_and(|kept| kept.contains(&(span.start() + offset)));
+      masked.push(if is_kept || !c.is_alphanumeric() { c } else { mask_char });
+    }
+    last = span.end();
+  }
+  masked.push_str(&text[last..]);
+  masked
+}
+
+pub(crate) fn check_template(regex: &Regex, template: &ReplacementTemplate) -> Result<(), VerexError> {
+  for reference in template.references() {
+    let exists = match *reference {
+      GroupRef::Index(index) => index < regex.captures_len(),
+      GroupRef::Name(ref name) => regex.capture_names().any(|n| n == Some(&name[..])),
+    };
+    if !exists {
+      return Err(VerexError::UnknownGroupReference {
+        reference: reference.clone(),
+        groups: regex.captures_len() - 1,
+      });
+    }
+  }
+  Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct CompiledVerex {
+  pub(crate) regex: Arc<Regex>,
+  pub(crate) pattern: Arc<str>,
+  pub(crate) literal: Option<Arc<str>>,
+  pub(crate) skip_empty: SkipEmpty,
+  pub(crate) inverted: bool,
+  pub(crate) flags: Flags,
+  memory_usage: Arc<OnceLock<Option<usize>>>,
+}
+
+/// The options a `CompiledVerex` was compiled with, on top of any inline flags in its pattern.
+/// The default is what `Verex::compile` uses: only `unicode` is set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Flags {
+  pub case_insensitive: bool,
+  pub multi_line: bool,
+  pub dot_matches_new_line: bool,
+  pub swap_greed: bool,
+  pub unicode: bool,
+  pub ignore_whitespace: bool,
+  pub crlf: bool,
+}
+
+impl Default for Flags {
+  fn default() -> Flags {
+    Flags {
+      case_insensitive: false,
+      multi_line: false,
+      dot_matches_new_line: false,
+      swap_greed: false,
+      unicode: true,
+      ignore_whitespace: false,
+      crlf: false,
+    }
+  }
+}
+
+impl Flags {
+  /// Sets or clears `flag`.
+  pub fn with(mut self, flag: Flag, enabled: bool) -> Flags {
+    match flag {
+      Flag::CaseInsensitive => self.case_insensitive = enabled,
+      Flag::MultiLine => self.multi_line = enabled,
+      Flag::DotMatchesNewline => self.dot_matches_new_line = enabled,
+      Flag::SwapGreed => self.swap_greed = enabled,
+      Flag::Unicode => self.unicode = enabled,
+      Flag::IgnoreWhitespace => self.ignore_whitespace = enabled,
+      Flag::CRLF => self.crlf = enabled,
+    }
+    self
+  }
+}
+
+/// Which operations of a `CompiledVerex` ignore zero-width matches.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SkipEmpty {
+  find_iter: bool,
+  split: bool,
+  replace_all: bool,
+}
+
+pub type SharedVerex = CompiledVerex;
+
+pub trait CaptureValue: Sized {
+  fn from_group(index: usize, group: Option<&str>) -> Result<Self, VerexError>;
+}
+
+impl CaptureValue for String {
+  fn from_group(index: usize, group: Option<&str>) -> Result<Self, VerexError> {
+    group.map(|x| x.to_string()).ok_or(VerexError::MissingGroup(index))
+  }
+}
+
+impl CaptureValue for Option<String> {
+  fn from_group(_: usize, group: Option<&str>) -> Result<Self, VerexError> {
+    Ok(group.map(|x| x.to_string()))
+  }
+}
+
+pub trait CaptureTuple: Sized {
+  fn arity() -> usize;
+  fn from_groups(groups: &[Option<&str>]) -> Result<Self, VerexError>;
+}
+
+macro_rules! capture_tuple {
+  ($arity:expr; $($t:ident $i:tt),+) => {
+    impl<$($t: CaptureValue),+> CaptureTuple for ($($t,)+) {
+      fn arity() -> usize {
+        $arity
+      }
+
+      fn from_groups(groups: &[Option<&str>]) -> Result<Self, VerexError> {
+        Ok(($($t::from_group($i + 1, groups[$i])?,)+))
+      }
+    }
+  }
+}
+
+capture_tuple!(1; A 0);
+capture_tuple!(2; A 0, B 1);
+capture_tuple!(3; A 0, B 1, C 2);
+capture_tuple!(4; A 0, B 1, C 2, D 3);
+capture_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+capture_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// A capture group referenced from a replacement string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupRef {
+  Index(usize),
+  Name(String),
+}
+
+impl fmt::Display for GroupRef {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      GroupRef::Index(index) => write!(f, "${{{}}}", index),
+      GroupRef::Name(ref name) => write!(f, "${{{}}}", name),
+    }
+  }
+}
+
+/// A replacement string along with the groups it references, following the `regex` crate's
+/// syntax: `$1`, `$name`, `${name}`, and `$$` for a literal dollar sign. As in `regex`, an
+/// unbraced reference takes the longest run of word characters, so `$1a` refers to a group
+/// named `1a`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplacementTemplate {
+  template: String,
+  references: Vec<GroupRef>,
+}
+
+impl ReplacementTemplate {
+  pub fn parse(template: &str) -> ReplacementTemplate {
+    let mut references = Vec::new();
+    let mut rest = template;
+    while let Some(i) = rest.find('$') {
+      rest = &rest[i + 1..];
+      let name = if rest.starts_with('$') {
+        rest = &rest[1..];
+        continue;
+      } else if rest.starts_with('{') {
+        match rest.find('}') {
+          Some(end) => {
+            let name = &rest[1..end];
+            rest = &rest[end + 1..];
+            name
+          },
+          None => continue,
+        }
+      } else {
+        let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let name = &rest[..end];
+        rest = &rest[end..];
+        name
+      };
+      if name.is_empty() {
+        continue;
+      }
+      references.push(match name.parse() {
+        Ok(index) => GroupRef::Index(index),
+        Err(_) => GroupRef::Name(name.to_string()),
+      });
+    }
+    ReplacementTemplate { template: template.to_string(), references }
+  }
+
+  pub fn references(&self) -> &[GroupRef] {
+    &self.references
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.template
+  }
+}
+
+/// Size and shape of a compiled pattern, as reported by `CompiledVerex::stats`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerexStats {
+  /// The length of the generated pattern in bytes.
+  pub pattern_len: usize,
+  /// The number of capture groups, not counting the implicit group of the whole match.
+  pub capture_groups: usize,
+  /// Whether the pattern begins with `^` or `\A`, after any leading flags.
+  pub has_anchor_start: bool,
+  /// Text every match starts with, as far as it can be read off the generated pattern: the
+  /// leading unquantified literals. This is a lower bound; a longer prefix may go unnoticed.
+  pub literal_prefix: Option<String>,
+  /// An estimate of the heap memory used by the regex engine. The regex crate itself does not
+  /// report it, so this is always `None` unless the `dfa` feature is enabled; see
+  /// `CompiledVerex::memory_usage`.
+  pub heap_bytes: Option<usize>,
+}
+
+/// Skips inline flag groups such as `(?m)` or `(?i)` at the start of `pattern`.
+fn skip_flags(mut pattern: &str) -> &str {
+  while pattern.starts_with("(?") {
+    match pattern.find(')') {
+      Some(end) if pattern[2..end].chars().all(|c| c.is_ascii_alphabetic() || c == '-') => pattern = &pattern[end + 1..],
+      _ => break,
+    }
+  }
+  pattern
+}
+
+/// Whether two occurrences of `literal` can overlap, as `aa` does in `aaa`, which is when it
+/// starts with one of its proper suffixes.
+fn overlaps_itself(literal: &str) -> bool {
+  let bytes = literal.as_bytes();
+  (1..bytes.len()).any(|len| bytes[..len] == bytes[bytes.len() - len..])
+}
+
+/// Whether the inline flag groups at the start of `pattern` leave `i` turned on.
+fn ignores_case(pattern: &str) -> bool {
+  let flags = &pattern[..pattern.len() - skip_flags(pattern).len()];
+  flags.split(')').fold(false, |on, group| {
+    let group = group.trim_start_matches("(?");
+    let (set, cleared) = group.split_at(group.find('-').unwrap_or(group.len()));
+    if set.contains('i') { true } else if cleared.contains('i') { false } else { on }
+  })
+}
+
+/// Approximate heap memory used by `pattern` compiled with `flags`, as measured by a
+/// `regex-automata` build of it.
+#[cfg(feature = "dfa")]
+fn measure(pattern: &str, flags: Flags) -> Option<usize> {
+  let syntax = regex_automata::util::syntax::Config::new()
+    .case_insensitive(flags.case_insensitive)
+    .multi_line(flags.multi_line)
+    .dot_matches_new_line(flags.dot_matches_new_line)
+    .swap_greed(flags.swap_greed)
+    .unicode(flags.unicode)
+    .ignore_whitespace(flags.ignore_whitespace)
+    .crlf(flags.crlf);
+  regex_automata::meta::Regex::builder().syntax(syntax).build(pattern).ok().map(|regex| regex.memory_usage())
+}
+
+#[cfg(not(feature = "dfa"))]
+fn measure(_pattern: &str, _flags: Flags) -> Option<usize> {
+  None
+}
+
+/// Text every match starts with, or `None` if there is none or the pattern ignores case.
+fn literal_prefix(pattern: &str) -> Option<String> {
+  if ignores_case(pattern) {
+    return None;
+  }
+  let mut rest = skip_flags(pattern);
+  for anchor in &["^", "\\A"] {
+    if rest.starts_with(anchor) {
+      rest = &rest[anchor.len()..];
+    }
+  }
+
+  let mut prefix = String::new();
+  while rest.starts_with("(?:") {
+    let end = match rest.find(')') {
+      Some(end) => end,
+      None => break,
+    };
+    let literal = &rest[3..end];
+    let after = &rest[end + 1..];
+    if literal.is_empty() || regex::escape(literal) != literal || after.starts_with(|c| "?*+{".contains(c)) {
+      break;
+    }
+    prefix.push_str(literal);
+    rest = after;
+  }
+  if prefix.is_empty() { None } else { Some(prefix) }
+}
+
+impl CompiledVerex {
+  /// Wraps a `Regex` built elsewhere, such as one to compare a `CompiledVerex` against. It never
+  /// takes the literal fast path, which only the builder knows to apply, and `flags` reports the
+  /// defaults whatever options `regex` was built with.
+  pub fn from_regex(regex: Regex) -> CompiledVerex {
+    CompiledVerex::new(regex, None)
+  }
+
+  /// Wraps `regex`, compiled with the default options.
+  pub(crate) fn new(regex: Regex, literal: Option<Arc<str>>) -> CompiledVerex {
+    let pattern: Arc<str> = Arc::from(regex.as_str());
+    CompiledVerex { regex: Arc::new(regex), pattern, literal, skip_empty: SkipEmpty::default(), inverted: false, flags: Flags::default(), memory_usage: Arc::default() }
+  }
+
+  pub fn stats(&self) -> VerexStats {
+    let start = skip_flags(&self.pattern);
+    VerexStats {
+      pattern_len: self.pattern.len(),
+      capture_groups: self.regex.captures_len() - 1,
+      has_anchor_start: start.starts_with('^') || start.starts_with("\\A"),
+      literal_prefix: match self.literal {
+        Some(ref literal) => Some(literal.to_string()),
+        None if self.flags.case_insensitive => None,
+        None => literal_prefix(&self.pattern),
+      },
+      heap_bytes: self.memory_usage(),
+    }
+  }
+
+  /// Approximate heap memory used by the compiled pattern, in bytes. This needs the `dfa`
+  /// feature, which measures a `regex-automata` build of the same pattern the first time it is
+  /// asked for; clones share the result. Without the feature it is `None`.
+  pub fn memory_usage(&self) -> Option<usize> {
+    *self.memory_usage.get_or_init(|| measure(&self.pattern, self.flags))
+  }
+
+  /// Inverts `is_match` and the filters built on it, `retain_not_matching` and
+  /// `partition_by_match`, like `grep -v`. Searching and capturing are not affected.
+  pub fn with_inverted(mut self, inverted: bool) -> CompiledVerex {
+    self.inverted = inverted;
+    self
+  }
+
+  pub fn flags(&self) -> Flags {
+    self.flags
+  }
+
+  /// Compiles the same pattern again with `flags` instead of the current options. Inline flags
+  /// in the pattern, such as those set by `with_any_case`, still take precedence. The settings
+  /// of `with_inverted` and `skip_empty_matches` are kept.
+  pub fn with_flags(&self, flags: Flags) -> Result<CompiledVerex, VerexError> {
+    let regex = RegexBuilder::new(&self.pattern)
+      .case_insensitive(flags.case_insensitive)
+      .multi_line(flags.multi_line)
+      .dot_matches_new_line(flags.dot_matches_new_line)
+      .swap_greed(flags.swap_greed)
+      .unicode(flags.unicode)
+      .ignore_whitespace(flags.ignore_whitespace)
+      .crlf(flags.crlf)
+      .build()
+      .map_err(|error| VerexError::Compile { pattern: self.pattern.to_string(), error })?;
+    // The literal shortcut matches exactly, which only the default options agree with.
+    let literal = if flags == Flags::default() { self.literal.clone() } else { None };
+    Ok(CompiledVerex { regex: Arc::new(regex), literal, flags, memory_usage: Arc::default(), ..self.clone() })
+  }
+
+  /// The same pattern, compiled to ignore case.
+  pub fn case_insensitive_clone(&self) -> CompiledVerex {
+    self.with_flags(self.flags.with(Flag::CaseInsensitive, true))
+      .expect("a pattern that compiled also compiles ignoring case")
+  }
+
+  pub fn is_match(&self, text: &str) -> bool {
+    let found = match self.literal {
+      Some(ref literal) => text.contains(&literal[..]),
+      None => self.regex.is_match(text),
+    };
+    found != self.inverted
+  }
+
+  /// Removes the strings for which `is_match` holds from `texts`.
+  pub fn retain_not_matching(&self, texts: &mut Vec<String>) {
+    texts.retain(|text| !self.is_match(text));
+  }
+
+  /// Splits `texts` into those for which `is_match` holds and the others.
+  pub fn partition_by_match<I, S>(&self, texts: I) -> (Vec<S>, Vec<S>) where I: IntoIterator<Item = S>, S: AsRef<str> {
+    texts.into_iter().partition(|text| self.is_match(text.as_ref()))
+  }
+
+  pub fn is_literal(&self) -> bool {
+    self.literal.is_some()
+  }
+
+  pub fn captures(&self, text: &str) -> Vec<String> {
+    match self.regex.captures(text) {
+      Some(captures) => captures.iter().map(|x| x.map_or("", |x| x.as_str()).to_string()).collect(),
+      None => Vec::new(),
+    }
+  }
+
+  /// Sets whether zero-width matches are ignored: `find_iter` skips them, `split` drops the
+  /// empty segments they leave and `replace_all` replaces nothing at them. By default none of
+  /// them is ignored, as in the `regex` crate. The `skip_empty_in_*` methods override this for
+  /// one operation.
+  pub fn skip_empty_matches(self, skip: bool) -> CompiledVerex {
+    self.skip_empty_in_find_iter(skip).skip_empty_in_split(skip).skip_empty_in_replace_all(skip)
+  }
+
+  pub fn skip_empty_in_find_iter(mut self, skip: bool) -> CompiledVerex {
+    self.skip_empty.find_iter = skip;
+    self
+  }
+
+  pub fn skip_empty_in_split(mut self, skip: bool) -> CompiledVerex {
+    self.skip_empty.split = skip;
+    self
+  }
+
+  pub fn skip_empty_in_replace_all(mut self, skip: bool) -> CompiledVerex {
+    self.skip_empty.replace_all = skip;
+    self
+  }
+
+  /// With the `tracing` feature, a `verex.find_iter` event reports the number of matches once
+  /// the iterator is exhausted.
+  pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Match<'a>> + 'a {
+    let skip = self.skip_empty.find_iter;
+    let matches = self.regex.find_iter(text).filter(move |m| !(skip && m.is_empty()));
+    #[cfg(feature = "tracing")]
+    let matches = instrument::count_matches(matches);
+    matches
+  }
+
+  /// The first match in `text[range]`, with offsets into the whole of `text`. Returns `None`
+  /// if `range` is out of bounds or does not start and end on char boundaries.
+  ///
+  /// The end of the range is treated as the end of the text, so `$` and `\b` match there as
+  /// they would at the end of the slice. The start is not: the text before it is still seen,
+  /// so `^` does not match at a start other than 0 (or, with `multiline`, one after a line
+  /// break), and `\b` matches there only if the text changes from word to non-word
+  /// characters or back.
+  pub fn find_within(&self, text: &str, range: Range<usize>) -> Option<(usize, usize)> {
+    self.find_iter_within(text, range).next()
+  }
+
+  /// The successive non-overlapping matches in `text[range]`, with offsets into the whole of
+  /// `text`. An invalid `range` has no matches; see `find_within` for how its edges behave.
+  pub fn find_iter_within<'a>(&'a self, text: &'a str, range: Range<usize>) -> impl Iterator<Item = (usize, usize)> + 'a {
+    let valid = range.start <= range.end && range.end <= text.len()
+      && text.is_char_boundary(range.start) && text.is_char_boundary(range.end);
+    let haystack = if valid { &text[..range.end] } else { "" };
+    let after = move |at: usize| haystack[at..].chars().next().map_or(haystack.len() + 1, |c| at + c.len_utf8());
+    let skip = self.skip_empty.find_iter;
+    let mut at = if valid { range.start } else { 1 };
+    let mut last_end = None;
+    // `Regex::find_iter` can't start at an offset, so step `find_at` along with its rule that
+    // an empty match right after the previous match is skipped.
+    iter::from_fn(move || loop {
+      if at > haystack.len() {
+        return None;
+      }
+      let m = self.regex.find_at(haystack, at)?;
+      if m.is_empty() && (last_end == Some(m.end()) || skip) {
+        at = after(m.end());
+        continue;
+      }
+      at = if m.is_empty() { after(m.end()) } else { m.end() };
+      last_end = Some(m.end());
+      return Some((m.start(), m.end()));
+    })
+  }
+
+  /// The last match that `find_iter` gives in `text`. A literal pattern that cannot overlap
+  /// itself is searched for from the end of the text. Other patterns are searched from the
+  /// start, without allocating: where the earlier matches end decides where the last one
+  /// starts, so a search from the end could find a match that `find_iter` skips.
+  pub fn rfind(&self, text: &str) -> Option<(usize, usize)> {
+    match self.literal {
+      Some(ref literal) if !literal.is_empty() && !overlaps_itself(literal) => {
+        text.rfind(&literal[..]).map(|start| (start, start + literal.len()))
+      },
+      _ => self.find_iter(text).last().map(|m| (m.start(), m.end())),
+    }
+  }
+
+  /// The text of the last match that `find_iter` gives in `text`. See `rfind`.
+  pub fn last_match(&self, text: &str) -> Option<String> {
+    self.rfind(text).map(|(start, end)| text[start..end].to_string())
+  }
+
+  pub fn split(&self, text: &str) -> Vec<String> {
+    let skip = self.skip_empty.split;
+    self.regex.split(text).filter(|x| !(skip && x.is_empty())).map(|x| x.to_string()).collect()
+  }
+
+  /// Splits `text` like `split`, but keeps the delimiters and their capture groups. Parts
+  /// alternate between text and delimiter, starting and ending with text, so a delimiter at
+  /// either end or two adjacent delimiters give an empty text part.
+  pub fn split_with_delimiters<'t>(&self, text: &'t str) -> Vec<SplitPart<'t>> {
+    split_with_delimiters(&self.regex, text)
+  }
+
+  /// Splits `text` like `split`, but not where the delimiter falls inside a section quoted with
+  /// one of `quote_chars`, such as `'` and `"` for command-line arguments. A backslash escapes
+  /// the character after it, so escaped quotes neither open nor close a section; an unterminated
+  /// quote runs to the end of the text. With `strip_quotes`, the quotes of closed sections are
+  /// removed, as are the backslashes escaping a quote or backslash inside them.
+  pub fn split_outside_quotes(&self, text: &str, quote_chars: &str, strip_quotes: bool) -> Vec<String> {
+    split_outside_quotes(&self.regex, text, quote_chars, strip_quotes)
+  }
+
+  pub fn replace(&self, text: &str, rep: &str) -> String {
+    self.replace_cow(text, rep).into_owned()
+  }
+
+  pub fn replace_cow<'t>(&self, text: &'t str, rep: &str) -> Cow<'t, str> {
+    self.regex.replace(text, rep)
+  }
+
+  pub fn replace_all(&self, text: &str, rep: &str) -> String {
+    self.replace_all_cow(text, rep).into_owned()
+  }
+
+  /// With the `tracing` feature, a `verex.replace_all` event reports the number of
+  /// replacements to a subscriber that records it.
+  pub fn replace_all_cow<'t>(&self, text: &'t str, rep: &str) -> Cow<'t, str> {
+    // Counting the replacements for `tracing` needs the loop below, so it is only taken when
+    // a subscriber would record them.
+    #[cfg(feature = "tracing")]
+    let counted = instrument::counts_replacements();
+    #[cfg(not(feature = "tracing"))]
+    let counted = false;
+    if !self.skip_empty.replace_all && !counted {
+      return self.regex.replace_all(text, rep);
+    }
+
+    let mut replaced = String::new();
+    let mut last = 0;
+    let mut count = 0;
+    for captures in self.regex.captures_iter(text) {
+      let m = captures.get(0).expect("group 0 always participates");
+      if m.is_empty() && self.skip_empty.replace_all {
+        continue;
+      }
+      replaced.push_str(&text[last..m.start()]);
+      captures.expand(rep, &mut replaced);
+      last = m.end();
+      count += 1;
+    }
+    #[cfg(feature = "tracing")]
+    instrument::replaced(count);
+    if count == 0 {
+      return Cow::Borrowed(text);
+    }
+    replaced.push_str(&text[last..]);
+    Cow::Owned(replaced)
+  }
+
+  /// Redacts every match in `text`, replacing each of its letters and digits with `mask_char` and
+  /// keeping separators such as spaces and dashes, so the text keeps its length in characters and
+  /// its shape. Any part of a match captured by a group named `keep` is left as it is.
+  pub fn mask(&self, text: &str, mask_char: char) -> String {
+    mask(&self.regex, text, mask_char, None)
+  }
+
+  /// Like `mask`, but only redacts capture group `group` of each match. Matches in which the
+  /// group does not take part are left as they are.
+  pub fn mask_group(&self, text: &str, group: usize, mask_char: char) -> String {
+    mask(&self.regex, text, mask_char, Some(group))
+  }
+
+  pub fn replace_checked(&self, text: &str, template: &ReplacementTemplate) -> Result<String, VerexError> {
+    check_template(&self.regex, template)?;
+    Ok(self.regex.replace(text, template.as_str()).into_owned())
+  }
+
+  /// Matches the pattern against each line of `text` separately, so anchors apply per line.
+  /// Lines end at `\n` or `\r\n`, and the line terminator is not part of the line.
+  pub fn match_any_line(&self, text: &str) -> bool {
+    text.lines().any(|line| self.regex.is_match(line))
+  }
+
+  pub fn matching_lines<'t>(&self, text: &'t str) -> Vec<&'t str> {
+    text.lines().filter(|line| self.regex.is_match(line)).collect()
+  }
+
+  /// The blocks captured by `Verex::between_lines` in each match, in the order of their groups
+  /// `block`, `block_2`, `block_3` and so on, or the whole match if the pattern has no such
+  /// group. An empty block gives an empty string.
+  pub fn extract_blocks(&self, text: &str) -> Vec<String> {
+    let blocks: Vec<usize> = self.regex.capture_names().enumerate()
+      .filter(|&(_, name)| name.is_some_and(is_block_group))
+      .map(|(index, _)| index)
+      .collect();
+    let groups = if blocks.is_empty() { vec![0] } else { blocks };
+    self.regex.captures_iter(text).flat_map(|captures| {
+      groups.iter().map(|&index| captures.get(index).map_or(String::new(), |m| m.as_str().to_string())).collect::<Vec<_>>()
+    }).collect()
+  }
+
+  pub fn find_first_named(&self, text: &str) -> Option<HashMap<String, String>> {
+    self.regex.captures(text).map(|captures| participating(named_groups(&self.regex, &captures)))
+  }
+
+  pub fn find_all_named(&self, text: &str) -> Vec<HashMap<String, String>> {
+    self.find_all_named_optional(text).into_iter().map(participating).collect()
+  }
+
+  pub fn find_all_named_optional(&self, text: &str) -> Vec<HashMap<String, Option<String>>> {
+    self.regex.captures_iter(text).map(|captures| named_groups(&self.regex, &captures)).collect()
+  }
+
+  /// The named groups of the first match as a JSON object, for handing to tools that take JSON.
+  /// Values are always strings, as matched, or `null` for a group that did not take part.
+  #[cfg(feature = "serde")]
+  pub fn captures_json(&self, text: &str) -> Option<Value> {
+    self.regex.captures(text).map(|captures| named_groups_json(&self.regex, &captures))
+  }
+
+  /// Like `captures_json`, but for every match, as a JSON array.
+  #[cfg(feature = "serde")]
+  pub fn find_all_json(&self, text: &str) -> Value {
+    Value::Array(self.regex.captures_iter(text).map(|captures| named_groups_json(&self.regex, &captures)).collect())
+  }
+
+  pub fn captures_tuple<T: CaptureTuple>(&self, text: &str) -> Result<Option<T>, VerexError> {
+    captures_tuple(&self.regex, text)
+  }
+
+  /// Splits `text` into matches and the gaps between them, in order. The segments cover the
+  /// whole input without overlapping, and no gap is empty.
+  pub fn tokenize(&self, text: &str) -> Vec<Segment> {
+    tokenize(&self.regex, text)
+  }
+
+  /// The span of every participating capture group of every match in `text`, in order, with
+  /// the whole match as group 0. Useful for highlighting matches piece by piece.
+  pub fn highlights(&self, text: &str) -> Vec<Highlight> {
+    highlights(&self.regex, text)
+  }
+
+  pub fn captures_iter<'r, 't>(&'r self, text: &'t str) -> CaptureMatches<'r, 't> {
+    self.regex.captures_iter(text)
+  }
+
+  /// Like `captures_iter`, but with the groups of each match copied into strings, and an empty
+  /// string for a group that did not take part.
+  pub fn captures_strings<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Vec<String>> + 'a {
+    self.regex.captures_iter(text).map(|captures| {
+      captures.iter().map(|x| x.map_or("", |x| x.as_str()).to_string()).collect()
+    })
+  }
+
+  /// Folds `f` over the captures of every match in `text`, without collecting them first.
+  pub fn scan<A, F>(&self, text: &str, init: A, mut f: F) -> A
+  where
+    F: FnMut(A, &Captures) -> A,
+  {
+    self.regex.captures_iter(text).fold(init, |acc, captures| f(acc, &captures))
+  }
+
+  /// Like `scan`, but stops at the first match for which `f` returns `ControlFlow::Break`.
+  pub fn try_scan<A, B, F>(&self, text: &str, init: A, mut f: F) -> ControlFlow<B, A>
+  where
+    F: FnMut(A, &Captures) -> ControlFlow<B, A>,
+  {
+    self.regex.captures_iter(text).try_fold(init, |acc, captures| f(acc, &captures))
+  }
+
+  /// The underlying compiled regex, for use with any `regex` API.
+  pub fn regex(&self) -> &Regex {
+    &self.regex
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.pattern
+  }
+
+  #[cfg(feature = "dfa")]
+  pub fn to_dense_dfa(&self) -> Result<DfaVerex, VerexError> {
+    let regex = regex_automata::dfa::regex::Regex::new(&self.pattern)
+      .map_err(|err| VerexError::Dfa(Box::new(err)))?;
+    Ok(DfaVerex { regex })
+  }
+}
+
+/// A matcher backed by fully compiled dense DFAs.
+///
+/// All states are built up front, which can take far longer and use far more memory than a
+/// `CompiledVerex`, especially for large Unicode classes; in return searching never has to
+/// build states lazily. Patterns a DFA can't express, such as Unicode word boundaries, fail to
+/// build with `VerexError::Dfa`.
+#[cfg(feature = "dfa")]
+#[derive(Clone, Debug)]
+pub struct DfaVerex {
+  regex: regex_automata::dfa::regex::Regex,
+}
+
+#[cfg(feature = "dfa")]
+impl DfaVerex {
+  pub fn is_match(&self, text: &str) -> bool {
+    self.regex.is_match(text)
+  }
+
+  pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+    self.regex.find(text).map(|m| (m.start(), m.end()))
+  }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "dfa")]
+use regex_automata;
+
+use builder::{Flag, Step, Unbounded, Verex};
+use classes::single_class;
+use compiled::GroupRef;
+
+#[derive(Debug)]
+pub enum VerexError {
+  Compile { pattern: String, error: regex::Error },
+  #[cfg(feature = "fancy")]
+  FancyCompile(fancy_regex::Error),
+  #[cfg(feature = "dfa")]
+  Dfa(Box<regex_automata::dfa::dense::BuildError>),
+  RequiresFancyBackend,
+  RequiresBytesBackend,
+  GroupCountMismatch { expected: usize, found: usize },
+  MissingGroup(usize),
+  MissingGroupNames { names: Vec<String> },
+  UnknownGroupReference { reference: GroupRef, groups: usize },
+  UnclosedGroups { count: usize },
+  UnbalancedGroup { step: usize },
+  InvalidRange { from: char, to: char },
+  InvalidRangeBound(String),
+  InvalidModifier { modifier: String, step: usize },
+  GlobalModifier { step: usize },
+  InvalidName { name: String, step: usize },
+  EmptyAlternation { step: usize },
+  EmptyClass { method: &'static str, step: usize },
+  UnknownDefinition { name: String, step: usize },
+  DefinitionCycle { names: Vec<String>, step: usize },
+  DuplicateGroupName { name: String, step: usize },
+  DuplicateTag { tag: String, step: usize },
+  NotACharacterClass { step: usize },
+  UnboundedRepetition { method: &'static str, step: usize },
+  UnsupportedExample(String),
+}
+
+impl VerexError {
+  /// The generated pattern that failed to compile, if any.
+  pub fn pattern(&self) -> Option<&str> {
+    match *self {
+      VerexError::Compile { ref pattern, .. } => Some(pattern),
+      _ => None,
+    }
+  }
+
+  /// The index of the builder step that produced the offending fragment, if known. Errors in
+  /// a nested builder such as `padded` point at the step that nests it.
+  pub fn step(&self) -> Option<usize> {
+    match *self {
+      VerexError::UnbalancedGroup { step }
+      | VerexError::InvalidModifier { step, .. }
+      | VerexError::GlobalModifier { step }
+      | VerexError::InvalidName { step, .. }
+      | VerexError::EmptyAlternation { step }
+      | VerexError::EmptyClass { step, .. }
+      | VerexError::UnknownDefinition { step, .. }
+      | VerexError::DefinitionCycle { step, .. }
+      | VerexError::DuplicateGroupName { step, .. }
+      | VerexError::DuplicateTag { step, .. }
+      | VerexError::NotACharacterClass { step }
+      | VerexError::UnboundedRepetition { step, .. } => Some(step),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for VerexError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      VerexError::Compile { ref error, .. } => write!(f, "failed to compile pattern: {}", error),
+      #[cfg(feature = "fancy")]
+      VerexError::FancyCompile(ref err) => write!(f, "failed to compile pattern: {}", err),
+      #[cfg(feature = "dfa")]
+      VerexError::Dfa(ref err) => write!(f, "failed to build DFA: {}", err),
+      VerexError::RequiresFancyBackend => write!(f, "pattern uses lookaround, which requires the `fancy` backend"),
+      VerexError::RequiresBytesBackend => write!(f, "pattern matches bytes outside ASCII, which only try_as_bytes_regex accepts"),
+      VerexError::GroupCountMismatch { expected, found } => {
+        write!(f, "expected {} capture groups, but the pattern has {}", expected, found)
+      },
+      VerexError::MissingGroup(index) => write!(f, "capture group {} did not participate in the match", index),
+      VerexError::MissingGroupNames { ref names } => {
+        let names: Vec<String> = names.iter().map(|name| format!("{:?}", name)).collect();
+        write!(f, "pattern has no capture groups named {}", names.join(", "))
+      },
+      VerexError::UnknownGroupReference { ref reference, groups } => {
+        write!(f, "replacement references {}, which is not among the pattern's {} capture groups", reference, groups)
+      },
+      VerexError::UnclosedGroups { count } => {
+        write!(f, "{} capture groups are still open; close them with end_capture or enable auto_close_groups", count)
+      },
+      VerexError::UnbalancedGroup { step } => write!(f, "end_capture at step {} has no open group to close", step),
+      VerexError::InvalidRange { from, to } => write!(f, "range {:?}-{:?} is reversed", from, to),
+      VerexError::InvalidRangeBound(ref bound) => write!(f, "range bound {:?} must be a single character", bound),
+      VerexError::InvalidModifier { ref modifier, step } => {
+        write!(f, "modifier {:?} at step {} is not one of the flags i, m, s, x, u, U and R", modifier, step)
+      },
+      VerexError::GlobalModifier { step } => write!(
+        f,
+        "modifier at step {} uses \"g\", but the regex crate has no global flag; use replace_all or find_all_named to act on every match",
+        step
+      ),
+      VerexError::InvalidName { ref name, step } => write!(f, "group name {:?} at step {} is not valid", name, step),
+      VerexError::EmptyAlternation { step } => write!(f, "or at step {} has an empty alternative", step),
+      VerexError::EmptyClass { method, step } => write!(f, "{} at step {} has no characters to match", method, step),
+      VerexError::UnknownDefinition { ref name, step } => write!(f, "insert at step {} names {:?}, which is not defined", step, name),
+      VerexError::DefinitionCycle { ref names, step } => {
+        write!(f, "insert at step {} expands definitions in a cycle: {}", step, names.join(" -> "))
+      },
+      VerexError::DuplicateGroupName { ref name, step } => write!(
+        f,
+        "step {} adds a second group named {:?}; rename it, or suffix duplicates with duplicate_groups(DuplicateGroups::Suffix)",
+        step, name
+      ),
+      VerexError::DuplicateTag { ref tag, step } => {
+        write!(f, "step {} tags a second group {:?}, which tag_to_index would never find; give each group its own tag", step, tag)
+      },
+      VerexError::NotACharacterClass { step } => {
+        write!(f, "length_bounded at step {} needs a fragment of one character class, such as `digit` or `any_of`", step)
+      },
+      VerexError::UnboundedRepetition { method, step } => write!(
+        f,
+        "{} at step {} has no upper bound, which unbounded(Unbounded::Forbid) rejects; bound it with anything_up_to, something_up_to or count_range",
+        method, step
+      ),
+      VerexError::UnsupportedExample(ref construct) => write!(f, "cannot generate examples for {}", construct),
+    }
+  }
+}
+
+impl Error for VerexError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      VerexError::Compile { ref error, .. } => Some(error),
+      _ => None,
+    }
+  }
+}
+
+fn valid_modifier(modifier: &str) -> bool {
+  !modifier.is_empty() && modifier.chars().all(|c| Flag::from_letter(c).is_some())
+}
+
+fn valid_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) if c == '_' || c.is_alphabetic() => chars.all(|c| c == '_' || c == '.' || c == '[' || c == ']' || c.is_alphanumeric()),
+    _ => false,
+  }
+}
+
+/// Checks the recorded steps for mistakes that would otherwise surface as an opaque compile
+/// error or a pattern that silently matches something else. Nested steps are reported at the
+/// index of the step that nests them.
+pub(crate) fn validate_steps(steps: &[Step], outer: Option<usize>) -> Result<(), VerexError> {
+  validate_scope(steps, outer, Unbounded::Allow, &mut HashMap::new(), &mut Vec::new())
+}
+
+/// Validates `steps` with the definitions they can insert, which fragments built in closures
+/// share with the steps around them, and the `unbounded` policy in effect where they start.
+/// `expanding` holds the definitions being inserted.
+fn validate_scope(
+  steps: &[Step],
+  outer: Option<usize>,
+  mut unbounded: Unbounded,
+  definitions: &mut HashMap<String, Vec<Step>>,
+  expanding: &mut Vec<String>,
+) -> Result<(), VerexError> {
+  let mut open = 0usize;
+  for (i, step) in steps.iter().enumerate() {
+    let index = outer.unwrap_or(i);
+    match *step {
+      Step::Unbounded(policy) => unbounded = policy,
+      Step::Anything | Step::Something | Step::AnythingBut(_) | Step::SomethingBut(_) |
+      Step::AnythingButNot(_) | Step::SomethingButNot(_) |
+      Step::ZeroOrMore | Step::OneOrMore | Step::BetweenLines(..) if unbounded == Unbounded::Forbid => {
+        return Err(VerexError::UnboundedRepetition { method: step.name(), step: index });
+      },
+      Step::AddModifier(ref modifier) | Step::RemoveModifier(ref modifier) if modifier.contains('g') => {
+        return Err(VerexError::GlobalModifier { step: index });
+      },
+      Step::AddModifier(ref modifier) | Step::RemoveModifier(ref modifier) if !valid_modifier(modifier) => {
+        return Err(VerexError::InvalidModifier { modifier: modifier.clone(), step: index });
+      },
+      Step::BeginNamedCapture(ref name) if !valid_name(name) => {
+        return Err(VerexError::InvalidName { name: name.clone(), step: index });
+      },
+      Step::Alternation(ref alternation) => {
+        for steps in alternation.fragments() {
+          validate_scope(steps, Some(index), unbounded, &mut HashMap::new(), &mut Vec::new())?;
+        }
+      },
+      Step::DelimitedRecord(ref fields, _, _) => {
+        for steps in fields {
+          validate_scope(steps, Some(index), unbounded, &mut HashMap::new(), &mut Vec::new())?;
+        }
+      },
+      Step::BetweenLines(ref start, ref end) => {
+        for steps in [start, end] {
+          validate_scope(steps, Some(index), unbounded, &mut HashMap::new(), &mut Vec::new())?;
+        }
+      },
+      Step::Define(ref name, ref steps) => {
+        definitions.insert(name.clone(), steps.clone());
+      },
+      Step::Insert(ref name) => {
+        if let Some(start) = expanding.iter().position(|expanding| expanding == name) {
+          let mut names = expanding[start..].to_vec();
+          names.push(name.clone());
+          return Err(VerexError::DefinitionCycle { names, step: index });
+        }
+        let steps = definitions.get(name).cloned()
+          .ok_or_else(|| VerexError::UnknownDefinition { name: name.clone(), step: index })?;
+        expanding.push(name.clone());
+        validate_scope(&steps, Some(index), unbounded, &mut definitions.clone(), expanding)?;
+        expanding.pop();
+      },
+      Step::BeginCapture | Step::BeginNamedCapture(_) | Step::BeginCaptureTagged(_) => open += 1,
+      Step::EndCapture if open == 0 => return Err(VerexError::UnbalancedGroup { step: index }),
+      Step::EndCapture => open -= 1,
+      Step::Or(ref value) if value.is_empty() || i == 0 => return Err(VerexError::EmptyAlternation { step: index }),
+      Step::AnyOf(ref value) | Step::Any(ref value) | Step::AnythingBut(ref value) | Step::SomethingBut(ref value) |
+      Step::AnythingButNot(ref value) | Step::SomethingButNot(ref value) |
+      Step::NotStartingWith(ref value) | Step::NotEndingWith(ref value) if value.is_empty() => {
+        return Err(VerexError::EmptyClass { method: step.name(), step: index });
+      },
+      Step::Range(ref pairs) if pairs.is_empty() => return Err(VerexError::EmptyClass { method: step.name(), step: index }),
+      Step::ByteRange(from, to) if from > to => return Err(VerexError::EmptyClass { method: step.name(), step: index }),
+      Step::BytesOf(ref bytes) if bytes.is_empty() => return Err(VerexError::EmptyClass { method: step.name(), step: index }),
+      Step::LengthBounded(ref steps, ..) => {
+        validate_scope(steps, Some(index), unbounded, &mut definitions.clone(), expanding)?;
+        if single_class(&Verex::from_steps(steps).as_string()).is_none() {
+          return Err(VerexError::NotACharacterClass { step: index });
+        }
+      },
+      Step::Padded(ref steps) | Step::Capture(ref steps) | Step::RepeatFragment(ref steps, _) |
+      Step::RequireSomewhere(ref steps) | Step::Atomic(ref steps) => {
+        validate_scope(steps, Some(index), unbounded, &mut definitions.clone(), expanding)?;
+      },
+      Step::Concat(ref steps) => {
+        validate_scope(steps, Some(index), unbounded, &mut HashMap::new(), &mut Vec::new())?;
+      },
+      _ => {},
+    }
+  }
+  Ok(())
+}
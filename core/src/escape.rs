@@ -0,0 +1,21 @@
+use regex;
+
+/// Escapes every regex metacharacter in `text`, so the result matches `text` literally. Use it
+/// with `Verex::raw` when composing a pattern by hand.
+pub fn escape(text: &str) -> String {
+  regex::escape(text)
+}
+
+/// Escapes `text` for use between the brackets of a character class, so the class matches
+/// exactly the characters of `text`. Besides `]`, `\`, `^` and `-`, this escapes `[`, `&` and
+/// `~`, which the regex crate reads as nested classes and set operations.
+pub fn escape_class(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    if let ']' | '\\' | '^' | '-' | '[' | '&' | '~' = c {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use builder::{Step, Verex};
+use error::VerexError;
+
+/// How many times unbounded quantifiers such as `one_or_more` repeat in `Verex::examples`.
+const DEFAULT_MAX_REPEAT: u32 = 3;
+
+/// How many characters of a class are tried, in order of preference.
+const CLASS_SAMPLES: usize = 3;
+const PREFERRED_CHARS: &str = "a0A b1B_z9Z-.:/";
+
+impl Verex {
+  /// Generates up to `limit` distinct strings that the pattern matches, for documentation and
+  /// for exercising parsers downstream. See `examples_with`.
+  pub fn examples(&self, limit: usize) -> Result<Vec<String>, VerexError> {
+    self.examples_with(limit, DEFAULT_MAX_REPEAT)
+  }
+
+  /// Generates up to `limit` distinct strings that the pattern matches. Classes are sampled for
+  /// a few readable characters, quantifiers repeat up to `max_repeat` times (or their minimum,
+  /// if that is higher), and every branch of an alternation is taken in turn.
+  ///
+  /// Assertions such as `\b` and anchors are skipped while generating, so examples that do not
+  /// satisfy them are left out; a pattern that can never match gives no examples. Raw fragments
+  /// added with `raw`, lookarounds, even those with a fallback for the default backend, and
+  /// classes of non-ASCII bytes are reported as `VerexError::UnsupportedExample`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if an example does not match the pattern, which would be a bug in the generator.
+  pub fn examples_with(&self, limit: usize, max_repeat: u32) -> Result<Vec<String>, VerexError> {
+    if adds_raw(self.steps()) {
+      return Err(VerexError::UnsupportedExample("a raw fragment".to_string()));
+    }
+    if self.has_lookaround() {
+      return Err(VerexError::UnsupportedExample("a lookaround".to_string()));
+    }
+    let regex = self.try_as_regex()?;
+    let hir = regex_syntax::parse(regex.as_str()).map_err(|err| VerexError::UnsupportedExample(err.to_string()))?;
+    let examples = generate(&hir, limit, max_repeat)?;
+
+    // Without assertions every example matches by construction; with them, some may not.
+    if hir.properties().look_set().is_empty() {
+      assert!(examples.iter().all(|example| regex.is_match(example)), "generated an example that does not match `{}`", regex);
+      return Ok(examples);
+    }
+    Ok(examples.into_iter().filter(|example| regex.is_match(example)).collect())
+  }
+}
+
+/// Whether any of `steps`, or of the fragments and definitions they nest, is a `raw` fragment.
+fn adds_raw(steps: &[Step]) -> bool {
+  steps.iter().any(|step| match *step {
+    Step::Raw(_) => true,
+    Step::Alternation(ref alternation) => alternation.fragments().into_iter().any(adds_raw),
+    Step::DelimitedRecord(ref fields, _, _) => fields.iter().any(|steps| adds_raw(steps)),
+    Step::BetweenLines(ref start, ref end) => adds_raw(start) || adds_raw(end),
+    Step::Padded(ref steps) | Step::Capture(ref steps) | Step::RepeatFragment(ref steps, _) |
+    Step::RequireSomewhere(ref steps) | Step::Atomic(ref steps) | Step::LengthBounded(ref steps, ..) |
+    Step::Concat(ref steps) | Step::Define(_, ref steps) => adds_raw(steps),
+    _ => false,
+  })
+}
+
+fn generate(hir: &Hir, limit: usize, max_repeat: u32) -> Result<Vec<String>, VerexError> {
+  match *hir.kind() {
+    HirKind::Empty | HirKind::Look(_) => Ok(vec![String::new()]),
+    HirKind::Literal(ref literal) => String::from_utf8(literal.0.to_vec())
+      .map(|literal| vec![literal])
+      .map_err(|_| VerexError::UnsupportedExample("a literal that is not valid UTF-8".to_string())),
+    HirKind::Class(Class::Unicode(ref class)) => {
+      let ranges: Vec<(char, char)> = class.iter().map(|range| (range.start(), range.end())).collect();
+      Ok(sample(&ranges, limit))
+    },
+    // Empty classes, which never match, and ASCII-only classes may come out as bytes.
+    HirKind::Class(Class::Bytes(ref class)) => match class.to_unicode_class() {
+      Some(class) => Ok(sample(&class.iter().map(|range| (range.start(), range.end())).collect::<Vec<_>>(), limit)),
+      None => Err(VerexError::UnsupportedExample("a class of non-ASCII bytes".to_string())),
+    },
+    HirKind::Capture(ref capture) => generate(&capture.sub, limit, max_repeat),
+    HirKind::Repetition(ref repetition) => {
+      let sub = generate(&repetition.sub, limit, max_repeat)?;
+      let most = repetition.max.unwrap_or(u32::MAX).min(max_repeat.max(repetition.min));
+      let counts: Vec<Vec<String>> = (repetition.min..=most).map(|n| {
+        (0..n).fold(vec![String::new()], |acc, _| product(&acc, &sub, limit))
+      }).collect();
+      Ok(interleave(&counts, limit))
+    },
+    HirKind::Concat(ref parts) => {
+      let mut acc = vec![String::new()];
+      for part in parts {
+        acc = product(&acc, &generate(part, limit, max_repeat)?, limit);
+      }
+      Ok(acc)
+    },
+    HirKind::Alternation(ref branches) => {
+      let branches = branches.iter().map(|branch| generate(branch, limit, max_repeat)).collect::<Result<Vec<_>, _>>()?;
+      Ok(interleave(&branches, limit))
+    },
+  }
+}
+
+/// Picks a few characters in `ranges`, preferring readable ones.
+fn sample(ranges: &[(char, char)], limit: usize) -> Vec<String> {
+  let contains = |c: char| ranges.iter().any(|&(start, end)| start <= c && c <= end);
+  let candidates = PREFERRED_CHARS.chars()
+    .chain((0x20u8..0x7f).map(char::from))
+    .chain(ranges.iter().map(|&(start, _)| start));
+  let mut chars: Vec<char> = Vec::new();
+  for c in candidates.filter(|&c| contains(c)) {
+    if chars.len() == CLASS_SAMPLES.min(limit) {
+      break;
+    }
+    if !chars.contains(&c) {
+      chars.push(c);
+    }
+  }
+  chars.into_iter().map(String::from).collect()
+}
+
+/// Concatenates every string of `a` with every string of `b`, in order of the sum of their
+/// indices, so that a cut-off result still varies both sides.
+fn product(a: &[String], b: &[String], limit: usize) -> Vec<String> {
+  let mut seen = HashSet::new();
+  let mut out = Vec::new();
+  if a.is_empty() || b.is_empty() {
+    return out;
+  }
+  for sum in 0..a.len() + b.len() - 1 {
+    for i in sum.saturating_sub(b.len() - 1)..=sum.min(a.len() - 1) {
+      if out.len() == limit {
+        return out;
+      }
+      let joined = a[i].clone() + &b[sum - i];
+      if seen.insert(joined.clone()) {
+        out.push(joined);
+      }
+    }
+  }
+  out
+}
+
+/// Takes one string from each list in turn, so that every list is represented.
+fn interleave(lists: &[Vec<String>], limit: usize) -> Vec<String> {
+  let mut seen = HashSet::new();
+  let mut out = Vec::new();
+  let longest = lists.iter().map(Vec::len).max().unwrap_or(0);
+  for i in 0..longest {
+    for list in lists {
+      if out.len() == limit {
+        return out;
+      }
+      if let Some(s) = list.get(i) {
+        if seen.insert(s.clone()) {
+          out.push(s.clone());
+        }
+      }
+    }
+  }
+  out
+}
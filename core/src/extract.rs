@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use regex::{Captures, Regex};
+
+use builder::Verex;
+use error::VerexError;
+
+/// Declares which named groups to pull out of a pattern's matches, and in what order.
+///
+/// Names are resolved to group indices once, by `build`, so extraction keeps working when groups
+/// are added to the pattern elsewhere.
+#[derive(Clone, Debug)]
+pub struct Extractor {
+  verex: Verex,
+  fields: Vec<(String, Option<String>)>,
+}
+
+/// An `Extractor` whose fields have been resolved against the compiled pattern.
+#[derive(Clone, Debug)]
+pub struct CompiledExtractor {
+  regex: Arc<Regex>,
+  fields: Vec<(usize, Option<String>)>,
+}
+
+impl Extractor {
+  pub fn new(verex: &Verex) -> Extractor {
+    Extractor { verex: verex.clone(), fields: Vec::new() }
+  }
+
+  /// Extracts the group named `name`, or an empty string if it does not take part in a match.
+  pub fn field(mut self, name: &str) -> Extractor {
+    self.fields.push((name.to_string(), None));
+    self
+  }
+
+  /// Extracts the group named `name`, or `default` if it does not take part in a match.
+  pub fn field_or(mut self, name: &str, default: &str) -> Extractor {
+    self.fields.push((name.to_string(), Some(default.to_string())));
+    self
+  }
+
+  /// Compiles the pattern and resolves the field names, reporting all the names the pattern
+  /// does not have at once.
+  pub fn build(self) -> Result<CompiledExtractor, VerexError> {
+    let compiled = self.verex.compile()?;
+    let mut fields = Vec::new();
+    let mut missing = Vec::new();
+    for (name, default) in self.fields {
+      match compiled.regex.capture_names().position(|n| n == Some(&name[..])) {
+        Some(index) => fields.push((index, default)),
+        None => missing.push(name),
+      }
+    }
+    if !missing.is_empty() {
+      return Err(VerexError::MissingGroupNames { names: missing });
+    }
+    Ok(CompiledExtractor { regex: compiled.regex, fields })
+  }
+}
+
+impl CompiledExtractor {
+  /// The fields of the first match in `text`, in the order they were declared.
+  pub fn extract(&self, text: &str) -> Option<Vec<String>> {
+    self.regex.captures(text).map(|captures| self.values(&captures))
+  }
+
+  /// The fields of every match in `text`.
+  pub fn extract_all(&self, text: &str) -> Vec<Vec<String>> {
+    self.regex.captures_iter(text).map(|captures| self.values(&captures)).collect()
+  }
+
+  fn values(&self, captures: &Captures) -> Vec<String> {
+    self.fields.iter()
+      .map(|&(index, ref default)| match captures.get(index) {
+        Some(group) => group.as_str().to_string(),
+        None => default.clone().unwrap_or_default(),
+      })
+      .collect()
+  }
+}
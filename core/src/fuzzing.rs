@@ -0,0 +1,165 @@
+//! A deterministic interpreter from bytes to builder calls, for fuzzing with tools such as
+//! cargo-fuzz:
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| verbal_expressions::fuzzing::check_ops_bytes(data));
+//! ```
+
+use builder::{Rep, Verex};
+
+/// Every call consumes at least one byte, but inputs are also cut off after this many calls.
+const MAX_OPS: usize = 64;
+const MAX_STR_LEN: usize = 8;
+const MAX_COUNT: u8 = 8;
+
+/// Characters for string arguments, weighted towards those that need escaping.
+const ALPHABET: &[u8] = b"ab9_ .*+?()[]{}|^$\\-,/\n";
+
+struct Ops<'a> {
+  data: &'a [u8],
+}
+
+impl<'a> Ops<'a> {
+  fn byte(&mut self) -> u8 {
+    match self.data.split_first() {
+      Some((&byte, rest)) => {
+        self.data = rest;
+        byte
+      },
+      None => 0,
+    }
+  }
+
+  fn count(&mut self) -> u8 {
+    self.byte() % MAX_COUNT
+  }
+
+  fn string(&mut self) -> String {
+    let len = self.byte() as usize % (MAX_STR_LEN + 1);
+    (0..len).map(|_| ALPHABET[self.byte() as usize % ALPHABET.len()] as char).collect()
+  }
+
+  fn char(&mut self) -> char {
+    ALPHABET[self.byte() as usize % ALPHABET.len()] as char
+  }
+
+  fn rep(&mut self) -> Rep {
+    match self.byte() % 6 {
+      0 => Rep::Optional,
+      1 => Rep::ZeroOrMore,
+      2 => Rep::OneOrMore,
+      3 => Rep::Exactly(u32::from(self.count())),
+      4 => Rep::AtLeast(u32::from(self.count())),
+      _ => Rep::Between(u32::from(self.count()), u32::from(self.count())),
+    }
+  }
+}
+
+impl Verex {
+  /// Interprets `data` as a sequence of builder calls with bounded arguments. The same bytes
+  /// always give the same expression, and every input terminates. Calls are chosen only among
+  /// those that never panic, but the result may well be invalid: unbalanced groups, bad names
+  /// and modifiers, and counts out of order are all reachable.
+  pub fn from_ops_bytes(data: &[u8]) -> Verex {
+    let mut ops = Ops { data };
+    let mut v = Verex::new();
+    for _ in 0..MAX_OPS {
+      if ops.data.is_empty() {
+        break;
+      }
+      v = match ops.byte() % 32 {
+        0 => v.start_of_line(),
+        1 => v.end_of_line(),
+        2 => v.then(&ops.string()),
+        3 => v.maybe(&ops.string()),
+        4 => v.anything(),
+        5 => v.something(),
+        6 => v.anything_but(&ops.string()),
+        7 => v.something_but(&ops.string()),
+        8 => v.any_of(&ops.string()),
+        9 => {
+          let (a, b) = (ops.char(), ops.char());
+          v.range(&[(a.min(b), a.max(b))])
+        },
+        10 => v.digit(),
+        11 => v.word(),
+        12 => v.space(),
+        13 => v.line_break(),
+        14 => v.or(&ops.string()),
+        15 => v.begin_capture(),
+        16 => v.begin_named_capture(&ops.string()),
+        17 => v.end_capture(),
+        18 => v.one_or_more(),
+        19 => v.zero_or_more(),
+        20 => v.count(i32::from(ops.count())),
+        21 => v.count_range(i32::from(ops.count()), i32::from(ops.count())),
+        22 => v.at_least(i32::from(ops.count())),
+        23 => v.lazy(),
+        24 => v.with_any_case(),
+        25 => v.with_specific_case(),
+        26 => v.search_multiline(),
+        27 => v.add_modifier(&ops.char().to_string()),
+        28 => v.raw(&ops.string()),
+        29 => v.until(&ops.string()),
+        30 => v.digit_n(ops.rep()),
+        _ => v.times(ops.rep()),
+      };
+    }
+    v
+  }
+}
+
+/// Builds the expression for `data` and checks that it behaves: compiling it either succeeds or
+/// reports a `VerexError`, never panics, and a successful compile agrees with the recorded steps
+/// and can be run.
+///
+/// # Panics
+///
+/// Panics if any of those checks fails, which is how a fuzzer recognizes a finding.
+pub fn check_ops_bytes(data: &[u8]) {
+  let v = Verex::from_ops_bytes(data);
+  let replayed = Verex::from_steps(v.steps());
+  assert_eq!(replayed.clone().as_string(), v.clone().as_string(), "steps do not replay to the same pattern");
+  match v.compile() {
+    Ok(compiled) => {
+      assert_eq!(compiled.as_str(), v.clone().as_string());
+      let haystack = String::from_utf8_lossy(data);
+      compiled.is_match(&haystack);
+      compiled.captures(&haystack);
+    },
+    Err(err) => assert!(!err.to_string().is_empty(), "error without a message: {:?}", err),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::check_ops_bytes;
+  use builder::Verex;
+
+  #[test]
+  fn test_from_ops_bytes() {
+    assert_eq!(Verex::from_ops_bytes(&[]).as_string(), "");
+    assert_eq!(Verex::from_ops_bytes(&[0, 2, 2, 0, 1, 18, 1]).as_string(), "^(?:ab)+$");
+    assert_eq!(Verex::from_ops_bytes(&[10, 20, 3]).as_string(), Verex::new().digit().count(3).as_string());
+    assert_eq!(Verex::from_ops_bytes(&[15, 11]).compile().unwrap_err().to_string(), Verex::new().begin_capture().word().compile().unwrap_err().to_string());
+
+    let inputs: &[&[u8]] = &[
+      b"",
+      b"\x00\x01\x02",
+      b"\x0f\x0f\x11",
+      b"\x10\x03a b\x0b\x11",
+      b"\x15\x07\x02\x1b\x05",
+      b"\x1c\x03([\x9a",
+      b"\x0e\x00\x0e\x00",
+      b"\xff\xfe\xfd\xfc\xfb\xfa\xf9\xf8",
+      &[7; 300],
+    ];
+    for data in inputs {
+      check_ops_bytes(data);
+    }
+    for seed in 0..=255u8 {
+      let data: Vec<u8> = (0..40u8).map(|i| seed.wrapping_mul(31).wrapping_add(i.wrapping_mul(seed | 1))).collect();
+      check_ops_bytes(&data);
+    }
+  }
+}
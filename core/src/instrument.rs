@@ -0,0 +1,60 @@
+//! Spans and events emitted with the `tracing` feature, so that a service can see which of its
+//! patterns are expensive to compile or run.
+
+use std::time::Instant;
+use regex::Regex;
+use tracing::field;
+use tracing::Level;
+use tracing::span::EnteredSpan;
+
+/// A `verex.compile` span, open from `start` until `finish` records what was compiled.
+pub(crate) struct CompileSpan {
+  span: EnteredSpan,
+  start: Instant,
+}
+
+impl CompileSpan {
+  pub(crate) fn start() -> CompileSpan {
+    let span = info_span!("verex.compile", pattern_len = field::Empty, groups = field::Empty, duration_us = field::Empty);
+    CompileSpan { span: span.entered(), start: Instant::now() }
+  }
+
+  /// Records the pattern length in bytes, the number of capture groups, not counting the
+  /// whole match, and how long compiling took. A span that is dropped without this failed.
+  pub(crate) fn finish(self, regex: &Regex) {
+    let duration = self.start.elapsed().as_micros() as u64;
+    self.span.record("pattern_len", regex.as_str().len());
+    self.span.record("groups", regex.captures_len() - 1);
+    self.span.record("duration_us", duration);
+  }
+}
+
+/// Passes `matches` through, and emits a `verex.find_iter` event with how many there were once
+/// they run out. An iterator that is dropped early emits nothing.
+pub(crate) fn count_matches<I: Iterator>(mut matches: I) -> impl Iterator<Item = I::Item> {
+  let mut count = 0;
+  let mut done = false;
+  ::std::iter::from_fn(move || {
+    let next = matches.next();
+    match next {
+      Some(_) => count += 1,
+      None if !done => {
+        done = true;
+        debug!(matches = count, "verex.find_iter");
+      },
+      None => {},
+    }
+    next
+  })
+}
+
+/// Whether a subscriber would record the `verex.replace_all` event, which is what makes
+/// counting the replacements worth doing.
+pub(crate) fn counts_replacements() -> bool {
+  enabled!(Level::DEBUG, replacements)
+}
+
+/// Emits a `verex.replace_all` event with how many matches were replaced.
+pub(crate) fn replaced(count: usize) {
+  debug!(replacements = count, "verex.replace_all");
+}
@@ -0,0 +1,295 @@
+use std::cmp;
+use std::ops::Range;
+use std::sync::Arc;
+use regex::{Captures, Regex};
+
+use compiled::CompiledVerex;
+
+/// Finds matches in input that arrives in chunks, such as data read from a socket.
+///
+/// Matches are assumed to be at most `max_match_len` bytes long. Only the input that could still
+/// take part in a match is kept between calls to `push`, so a longer match may be reported
+/// truncated or not at all. A match is reported as soon as no further input can change it; call
+/// `finish` at the end of the stream to flush the rest.
+#[derive(Clone, Debug)]
+pub struct StreamMatcher {
+  regex: Arc<Regex>,
+  max_match_len: usize,
+  buffer: String,
+  offset: usize,
+  search_from: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamMatch {
+  pub start: usize,
+  pub end: usize,
+  pub text: String,
+}
+
+/// A piece of the input as split up by `CompiledVerex::tokenize`.
+///
+/// `captures` holds the capture groups of a match, starting at group 1; a group that did not
+/// take part in the match is `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+  Match { range: Range<usize>, text: String, captures: Vec<Option<String>> },
+  Gap { range: Range<usize>, text: String },
+}
+
+impl Segment {
+  pub fn range(&self) -> Range<usize> {
+    match *self {
+      Segment::Match { ref range, .. } | Segment::Gap { ref range, .. } => range.clone(),
+    }
+  }
+
+  pub fn text(&self) -> &str {
+    match *self {
+      Segment::Match { ref text, .. } | Segment::Gap { ref text, .. } => text,
+    }
+  }
+
+  pub fn is_match(&self) -> bool {
+    match *self {
+      Segment::Match { .. } => true,
+      Segment::Gap { .. } => false,
+    }
+  }
+}
+
+pub(crate) fn tokenize(regex: &Regex, text: &str) -> Vec<Segment> {
+  let mut segments = Vec::new();
+  let mut last = 0;
+  for captures in regex.captures_iter(text) {
+    let m = captures.get(0).expect("group 0 always participates");
+    if last < m.start() {
+      segments.push(Segment::Gap { range: last..m.start(), text: text[last..m.start()].to_string() });
+    }
+    segments.push(Segment::Match {
+      range: m.range(),
+      text: m.as_str().to_string(),
+      captures: captures.iter().skip(1).map(|group| group.map(|g| g.as_str().to_string())).collect(),
+    });
+    last = m.end();
+  }
+  if last < text.len() {
+    segments.push(Segment::Gap { range: last..text.len(), text: text[last..].to_string() });
+  }
+  segments
+}
+
+/// Either the text between two delimiters or a delimiter itself, as returned in order by
+/// `CompiledVerex::split_with_delimiters`. Both borrow from the input, and a `Text` part may be
+/// empty. A `Delimiter` keeps what its capture groups matched, group 1 first, with `None` for a
+/// group that did not take part.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SplitPart<'t> {
+  Text(&'t str),
+  Delimiter { text: &'t str, captures: Vec<Option<&'t str>> },
+}
+
+impl<'t> SplitPart<'t> {
+  pub fn text(&self) -> &'t str {
+    match *self {
+      SplitPart::Text(text) | SplitPart::Delimiter { text, .. } => text,
+    }
+  }
+
+  pub fn is_delimiter(&self) -> bool {
+    match *self {
+      SplitPart::Text(_) => false,
+      SplitPart::Delimiter { .. } => true,
+    }
+  }
+}
+
+pub(crate) fn split_with_delimiters<'t>(regex: &Regex, text: &'t str) -> Vec<SplitPart<'t>> {
+  let mut parts = Vec::new();
+  let mut last = 0;
+  for captures in regex.captures_iter(text) {
+    let m = captures.get(0).expect("group 0 always participates");
+    parts.push(SplitPart::Text(&text[last..m.start()]));
+    parts.push(SplitPart::Delimiter {
+      text: m.as_str(),
+      captures: captures.iter().skip(1).map(|group| group.map(|g| g.as_str())).collect(),
+    });
+    last = m.end();
+  }
+  parts.push(SplitPart::Text(&text[last..]));
+  parts
+}
+
+/// A quoted section found by `quoted_regions`, including its quotes.
+struct QuotedRegion {
+  range: Range<usize>,
+  quote: char,
+  terminated: bool,
+}
+
+/// Scans `text` for sections quoted with any of `quote_chars`. A backslash escapes the next
+/// character, so an escaped quote neither opens nor closes a section. A quote that is never
+/// closed runs to the end of the text.
+fn quoted_regions(text: &str, quote_chars: &str) -> Vec<QuotedRegion> {
+  let mut regions = Vec::new();
+  let mut open: Option<(usize, char)> = None;
+  let mut chars = text.char_indices();
+  while let Some((i, c)) = chars.next() {
+    match open {
+      _ if c == '\\' => {
+        chars.next();
+      },
+      Some((start, quote)) if c == quote => {
+        regions.push(QuotedRegion { range: start..i + c.len_utf8(), quote, terminated: true });
+        open = None;
+      },
+      None if quote_chars.contains(c) => open = Some((i, c)),
+      _ => {},
+    }
+  }
+  if let Some((start, quote)) = open {
+    regions.push(QuotedRegion { range: start..text.len(), quote, terminated: false });
+  }
+  regions
+}
+
+/// Removes the quotes of the closed sections in `text[range]`, along with the backslashes that
+/// escape a quote or a backslash inside them.
+fn unquote(text: &str, range: Range<usize>, regions: &[QuotedRegion]) -> String {
+  let mut out = String::new();
+  let mut chars = text[range.clone()].char_indices().map(|(i, c)| (range.start + i, c)).peekable();
+  while let Some((i, c)) = chars.next() {
+    let region = regions.iter().find(|region| region.terminated && region.range.start <= i && i < region.range.end);
+    match region {
+      Some(region) if i == region.range.start || i + c.len_utf8() == region.range.end => {},
+      Some(region) if c == '\\' => match chars.peek() {
+        Some(&(_, next)) if next == region.quote || next == '\\' => {},
+        _ => out.push(c),
+      },
+      _ => out.push(c),
+    }
+  }
+  out
+}
+
+pub(crate) fn split_outside_quotes(regex: &Regex, text: &str, quote_chars: &str, strip_quotes: bool) -> Vec<String> {
+  let regions = quoted_regions(text, quote_chars);
+  let mut ranges = Vec::new();
+  let mut last = 0;
+  let mut pos = 0;
+  while let Some(m) = regex.find_at(text, pos) {
+    if let Some(region) = regions.iter().find(|region| m.start() < region.range.end && region.range.start < m.end()) {
+      pos = region.range.end;
+      continue;
+    }
+    ranges.push(last..m.start());
+    last = m.end();
+    pos = m.end() + if m.start() == m.end() { text[m.end()..].chars().next().map_or(1, char::len_utf8) } else { 0 };
+    if pos > text.len() {
+      break;
+    }
+  }
+  ranges.push(last..text.len());
+  ranges.into_iter().map(|range| if strip_quotes { unquote(text, range, &regions) } else { text[range].to_string() }).collect()
+}
+
+/// Byte offsets of every capture group in a match, for use on the items of
+/// `CompiledVerex::captures_iter`.
+pub trait GroupRanges {
+  /// The `(start, end)` offsets of each group, starting at group 0 for the whole match; a group
+  /// that did not take part in the match is `None`.
+  fn group_ranges(&self) -> Vec<Option<(usize, usize)>>;
+}
+
+impl<'t> GroupRanges for Captures<'t> {
+  fn group_ranges(&self) -> Vec<Option<(usize, usize)>> {
+    self.iter().map(|group| group.map(|g| (g.start(), g.end()))).collect()
+  }
+}
+
+/// The span of one capture group in a match, as returned by `CompiledVerex::highlights`. Group 0 is the
+/// whole match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Highlight {
+  pub group: usize,
+  pub name: Option<String>,
+  pub range: Range<usize>,
+}
+
+pub(crate) fn highlights(regex: &Regex, text: &str) -> Vec<Highlight> {
+  let names: Vec<Option<&str>> = regex.capture_names().collect();
+  let mut highlights = Vec::new();
+  for captures in regex.captures_iter(text) {
+    for (group, range) in captures.group_ranges().into_iter().enumerate() {
+      if let Some((start, end)) = range {
+        assert!(text.is_char_boundary(start) && text.is_char_boundary(end), "group {} is not on a char boundary", group);
+        highlights.push(Highlight { group, name: names[group].map(|name| name.to_string()), range: start..end });
+      }
+    }
+  }
+  highlights
+}
+
+impl StreamMatcher {
+  pub fn new(verex: &CompiledVerex, max_match_len: usize) -> StreamMatcher {
+    StreamMatcher {
+      regex: verex.regex.clone(),
+      max_match_len,
+      buffer: String::new(),
+      offset: 0,
+      search_from: 0,
+    }
+  }
+
+  pub fn push(&mut self, chunk: &str) -> Vec<StreamMatch> {
+    self.buffer.push_str(chunk);
+    let matches = self.collect(false);
+
+    let mut settled = self.buffer.len().saturating_sub(self.max_match_len);
+    while !self.buffer.is_char_boundary(settled) {
+      settled -= 1;
+    }
+    self.search_from = cmp::max(self.search_from, settled);
+
+    // keep one more character as context for assertions such as `\b`
+    let keep = self.buffer[..self.search_from].char_indices().next_back().map_or(0, |(i, _)| i);
+    self.buffer.drain(..keep);
+    self.offset += keep;
+    self.search_from -= keep;
+    matches
+  }
+
+  pub fn finish(&mut self) -> Vec<StreamMatch> {
+    let matches = self.collect(true);
+    self.offset += self.buffer.len();
+    self.buffer.clear();
+    self.search_from = 0;
+    matches
+  }
+
+  fn collect(&mut self, eof: bool) -> Vec<StreamMatch> {
+    let mut matches = Vec::new();
+    while let Some(m) = self.regex.find_at(&self.buffer, self.search_from) {
+      if !eof && m.start() + self.max_match_len >= self.buffer.len() {
+        break;
+      }
+      matches.push(StreamMatch {
+        start: self.offset + m.start(),
+        end: self.offset + m.end(),
+        text: m.as_str().to_string(),
+      });
+      if m.start() < m.end() {
+        self.search_from = m.end();
+      } else {
+        match self.buffer[m.end()..].chars().next() {
+          Some(c) => self.search_from = m.end() + c.len_utf8(),
+          None => {
+            self.search_from = m.end();
+            break;
+          },
+        }
+      }
+    }
+    matches
+  }
+}
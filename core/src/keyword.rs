@@ -0,0 +1,58 @@
+use regex;
+use regex::Regex;
+
+use builder::{Step, Verex, NO_MATCH};
+
+/// The pattern for `keywords`, ignoring case and delimited by word boundaries, with each keyword
+/// in a group of its own if `capture` is set. Empty keywords are left out.
+fn keyword_pattern(keywords: &[&str], capture: bool) -> String {
+  let alternatives: Vec<String> = keywords.iter()
+    .filter(|keyword| !keyword.is_empty())
+    .map(|keyword| if capture { format!("({})", regex::escape(keyword)) } else { regex::escape(keyword) })
+    .collect();
+  if alternatives.is_empty() {
+    return NO_MATCH.to_string();
+  }
+  format!(r"(?:\b(?i:{})\b)", alternatives.join("|"))
+}
+
+impl Verex {
+  /// Matches any of `keywords` as a whole word, ignoring case, such as the levels of a log line.
+  /// Keywords are matched literally. Empty keywords are ignored; with none left, nothing matches.
+  ///
+  /// The word boundaries only hold next to word characters, so a keyword that starts or ends
+  /// with punctuation, such as `c++`, is not matched there.
+  pub fn keyword_of(self, keywords: &[&str]) -> Verex {
+    let step = Step::KeywordOf(keywords.iter().map(|keyword| keyword.to_string()).collect());
+    self.record(step, |v| v.atom(&keyword_pattern(keywords, false)))
+  }
+}
+
+/// Finds keywords like `Verex::keyword_of`, and tells which of them was found.
+#[derive(Clone, Debug)]
+pub struct KeywordMatcher {
+  regex: Regex,
+  /// The index in the keywords of each capture group, starting at group 1.
+  indices: Vec<usize>,
+}
+
+impl KeywordMatcher {
+  pub fn new(keywords: &[&str]) -> KeywordMatcher {
+    let regex = Regex::new(&keyword_pattern(keywords, true)).expect("escaped keywords always compile");
+    let indices = keywords.iter().enumerate().filter(|&(_, keyword)| !keyword.is_empty()).map(|(i, _)| i).collect();
+    KeywordMatcher { regex, indices }
+  }
+
+  pub fn is_match(&self, text: &str) -> bool {
+    self.regex.is_match(text)
+  }
+
+  /// The index in the keywords of the first keyword found in `text`. If several keywords are
+  /// the same ignoring case, the first of them is reported.
+  pub fn which(&self, text: &str) -> Option<usize> {
+    let captures = self.regex.captures(text)?;
+    self.indices.iter().enumerate()
+      .find(|&(group, _)| captures.get(group + 1).is_some())
+      .map(|(_, &index)| index)
+  }
+}
@@ -0,0 +1,46 @@
+//! The builder behind `verbal_expressions`, in a crate of its own so that
+//! `verbal_expressions_macros` can build and check patterns while a macro is expanded; a
+//! proc-macro crate cannot depend on the crate that re-exports it. Use it through
+//! `verbal_expressions`, which re-exports every module.
+
+extern crate regex;
+extern crate regex_syntax;
+#[cfg(feature = "fancy")]
+extern crate fancy_regex;
+#[cfg(feature = "dfa")]
+extern crate regex_automata;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "tracing")]
+#[macro_use]
+extern crate tracing;
+#[cfg(feature = "unicode-normalization")]
+extern crate unicode_normalization;
+
+#[cfg(feature = "proptest")]
+mod arbitrary;
+pub mod alternation;
+pub mod builder;
+pub mod classes;
+pub mod compiled;
+pub mod error;
+pub mod escape;
+pub mod examples;
+pub mod extract;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+#[cfg(feature = "tracing")]
+mod instrument;
+pub mod iter;
+pub mod keyword;
+#[cfg(feature = "unicode-normalization")]
+pub mod normalize;
+mod parse;
+pub mod presets;
+pub mod scanner;
+pub mod strict;
+pub mod syntax;
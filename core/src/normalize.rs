@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+use builder::{Step, Verex};
+use compiled::CompiledVerex;
+
+/// `value` in Unicode Normalization Form C, borrowed if it already is.
+pub(crate) fn nfc(value: &str) -> Cow<'_, str> {
+  match is_nfc_quick(value.chars()) {
+    IsNormalized::Yes => Cow::Borrowed(value),
+    _ => Cow::Owned(value.nfc().collect()),
+  }
+}
+
+/// Text normalized to NFC, which remembers where each part of it came from in the original.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NfcText {
+  normalized: String,
+  original_len: usize,
+  /// The start of each run of characters that was normalized on its own, in the normalized
+  /// and the original text. Empty if the text was already normalized.
+  chunks: Vec<(usize, usize)>,
+}
+
+impl NfcText {
+  pub fn new(text: &str) -> NfcText {
+    if let Cow::Borrowed(text) = nfc(text) {
+      return NfcText { normalized: text.to_string(), original_len: text.len(), chunks: Vec::new() };
+    }
+
+    // A character that does not combine with what precedes it starts a new run. Only starters
+    // can, and of those only the ones that do not compose with the run.
+    let mut normalized = String::with_capacity(text.len());
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices().skip(1) {
+      if canonical_combining_class(c) != 0 {
+        continue;
+      }
+      let run = nfc(&text[start..i]);
+      if nfc(&text[start..i + c.len_utf8()]) == run.to_string() + &nfc(&c.to_string()) {
+        chunks.push((normalized.len(), start));
+        normalized.push_str(&run);
+        start = i;
+      }
+    }
+    chunks.push((normalized.len(), start));
+    normalized.push_str(&nfc(&text[start..]));
+    NfcText { normalized, original_len: text.len(), chunks }
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.normalized
+  }
+
+  /// The range of the original text that `range` of the normalized text came from. A range
+  /// that ends inside a character composed from several is widened to all of them.
+  pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+    if self.chunks.is_empty() {
+      return range;
+    }
+    let start = self.chunks.iter().rev().find(|&&(normalized, _)| normalized <= range.start).map_or(0, |&(_, original)| original);
+    let end = self.chunks.iter().find(|&&(normalized, _)| normalized >= range.end).map_or(self.original_len, |&(_, original)| original);
+    start..end
+  }
+}
+
+impl Verex {
+  /// With `enabled`, the values of later literal combinators, such as `then`, `find`, `maybe`,
+  /// `starts_with` and `until`, are normalized to NFC, so that they match text normalized with
+  /// `is_match_nfc` and `find_iter_nfc` however they were written.
+  pub fn normalize_literals(self, enabled: bool) -> Verex {
+    self.record(Step::NormalizeLiterals(enabled), |mut v| {
+      v.normalize_literals = enabled;
+      v
+    })
+  }
+}
+
+impl CompiledVerex {
+  /// Whether the pattern matches `text` normalized to NFC, so that a decomposed "é" in the
+  /// text matches a composed one in the pattern.
+  pub fn is_match_nfc(&self, text: &str) -> bool {
+    self.is_match(&nfc(text))
+  }
+
+  /// The matches in `text` normalized to NFC, as ranges of the normalized text, which is
+  /// returned with them. `NfcText::original_range` maps the ranges back onto `text`.
+  pub fn find_iter_nfc(&self, text: &str) -> (NfcText, Vec<Range<usize>>) {
+    let text = NfcText::new(text);
+    let ranges = self.find_iter(text.as_str()).map(|m| m.range()).collect();
+    (text, ranges)
+  }
+}
@@ -0,0 +1,312 @@
+use std::slice;
+use regex;
+use regex_syntax::ast::parse::Parser;
+use regex_syntax::ast::{self, Ast, AssertionKind, Flag, FlagsItemKind, GroupKind, RepetitionKind, RepetitionRange};
+
+use alternation::Alternation;
+use builder::{Step, Verex};
+
+/// The steps that build `pattern`, or `None` if it does not parse. Literals, groups, captures,
+/// quantifiers, alternations, anchors, flags and the combinators whose rendering is found as is
+/// are recovered; anything else is kept as a `FromRegex` step of its own text.
+pub(crate) fn steps(pattern: &str) -> Option<Vec<Step>> {
+  let ast = Parser::new().parse(pattern).ok()?;
+  Some(Reader { pattern }.fragment(&ast))
+}
+
+/// The combinators without arguments, recognized by their rendering.
+fn combinators() -> Vec<Step> {
+  vec![
+    Step::Anything, Step::Something, Step::LineBreak, Step::Tab, Step::Word, Step::NonWord, Step::Digit,
+    Step::NonDigit, Step::Space, Step::NonSpace, Step::OptionalWhitespace, Step::RequiredWhitespace,
+  ]
+}
+
+/// The combinator that renders exactly as `text`, if one does.
+fn combinator(text: &str) -> Option<Step> {
+  let mut candidates = combinators();
+  if let Some(class) = text.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+    candidates.push(Step::AnyOf(class.to_string()));
+  }
+  if let Some(class) = text.strip_prefix("(?:[^").and_then(|rest| rest.strip_suffix("]*)")) {
+    candidates.push(Step::AnythingBut(class.to_string()));
+  }
+  if let Some(class) = text.strip_prefix("(?:[^").and_then(|rest| rest.strip_suffix("]+)")) {
+    candidates.push(Step::SomethingBut(class.to_string()));
+  }
+  candidates.into_iter()
+    .find(|step| Verex::from_steps(slice::from_ref(step)).as_string() == text)
+}
+
+struct Reader<'a> {
+  pattern: &'a str,
+}
+
+impl<'a> Reader<'a> {
+  fn text(&self, span: &ast::Span) -> &'a str {
+    &self.pattern[span.start.offset..span.end.offset]
+  }
+
+  /// The steps of a fragment built on its own, such as the whole pattern or what a capture
+  /// holds, where a leading `(?m)` and the anchors at either end have combinators of their own.
+  fn fragment(&self, ast: &Ast) -> Vec<Step> {
+    if let Ast::Alternation(ref alternation) = *ast {
+      if let Some(steps) = self.or(alternation) {
+        return steps;
+      }
+    }
+    self.anchored(items(ast))
+  }
+
+  /// The steps of the items of a fragment, with its leading `(?m)` and anchors.
+  fn anchored(&self, mut asts: &[Ast]) -> Vec<Step> {
+    let mut steps = Vec::new();
+    if let Some(Ast::Flags(flags)) = asts.first() {
+      if let [ast::FlagsItem { kind: FlagsItemKind::Flag(Flag::MultiLine), .. }] = flags.flags.items[..] {
+        steps.push(Step::SearchMultiline);
+        asts = &asts[1..];
+      }
+    }
+    let (starts, asts, ends) = anchors(asts);
+    steps.extend(vec![Step::StartOfLine; starts]);
+    steps.extend(self.sequence(asts));
+    steps.extend(vec![Step::EndOfLine; ends]);
+    steps
+  }
+
+  /// The steps of a fragment that `or` split into captured branches, as in `^(a)|(b)$`, where
+  /// each branch after the first starts with the value given to `or`. Anchors added after the
+  /// first `or` end up inside the outer branches, and those added before it outside them.
+  fn or(&self, alternation: &ast::Alternation) -> Option<Vec<Step>> {
+    let last = alternation.asts.len().checked_sub(1).filter(|&last| last > 0)?;
+    let (mut steps, mut deferred) = (Vec::new(), Vec::new());
+    for (i, branch) in alternation.asts.iter().enumerate() {
+      let mut asts = items(branch);
+      if i == 0 {
+        let start = asts.len().checked_sub(1)?;
+        steps.extend(self.anchored(&asts[..start]));
+        asts = &asts[start..];
+      }
+      let trailing = if i == last { asts.get(1..).map_or(0, |rest| anchors(rest).2) } else { 0 };
+      let mut inner = match asts.first() {
+        Some(Ast::Group(group)) if is_numbered(group) && asts.len() == 1 + trailing => items(&group.ast),
+        _ => return None,
+      };
+      let (starts, _, ends) = anchors(inner);
+      if i == 0 {
+        deferred.extend(vec![Step::StartOfLine; starts]);
+        inner = &inner[starts..];
+      }
+      let mut end_anchors = Vec::new();
+      if i == last {
+        end_anchors.extend(vec![Step::EndOfLine; ends]);
+        inner = &inner[..inner.len() - ends];
+      }
+      let mut inner = self.sequence(inner);
+      if i > 0 {
+        let value = match inner.first() {
+          Some(Step::Then(value)) => value.clone(),
+          _ => return None,
+        };
+        inner[0] = Step::Or(value);
+        inner.splice(1..1, deferred.drain(..));
+      }
+      // An end anchor inside the last branch was added before the first `or`.
+      let first_or = steps.iter().position(|step| step.name() == "or").unwrap_or(steps.len());
+      steps.splice(first_or..first_or, end_anchors);
+      steps.extend(inner);
+      steps.extend(self.anchored(&asts[1..]));
+    }
+    Some(steps).filter(|steps| Verex::from_steps(steps).as_string() == self.text(&alternation.span))
+  }
+
+  /// The steps of consecutive items, with each run of literals read as one `then`.
+  fn sequence(&self, asts: &[Ast]) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut rest = asts;
+    while let Some(ast) = rest.first() {
+      let run = rest.iter().take_while(|ast| is_literal(ast)).count();
+      if run > 0 {
+        let start = ast.span().start.offset;
+        let end = rest[run - 1].span().end.offset;
+        steps.push(Step::Then(self.pattern[start..end].to_string()));
+        rest = &rest[run..];
+      } else {
+        steps.extend(self.node(ast));
+        rest = &rest[1..];
+      }
+    }
+    steps
+  }
+
+  fn node(&self, ast: &Ast) -> Vec<Step> {
+    let steps = match *ast {
+      Ast::Empty(_) => Some(Vec::new()),
+      Ast::Literal(_) => Some(vec![Step::Then(self.text(ast.span()).to_string())]),
+      Ast::Flags(ref flags) => Some(flag_steps(&flags.flags)),
+      Ast::Group(ref group) => Some(self.group(group)),
+      Ast::Repetition(ref repetition) => Some(self.repetition(repetition)),
+      Ast::Alternation(ref alternation) => Some(vec![self.alternation(alternation)]),
+      Ast::Concat(ref concat) => Some(vec![Step::Concat(self.sequence(&concat.asts))]),
+      _ => None,
+    };
+    steps.unwrap_or_else(|| {
+      let text = self.text(ast.span());
+      vec![combinator(text).unwrap_or_else(|| Step::FromRegex(text.to_string()))]
+    })
+  }
+
+  fn group(&self, group: &ast::Group) -> Vec<Step> {
+    match group.kind {
+      GroupKind::CaptureIndex(_) | GroupKind::CaptureName { .. } => {
+        let mut steps = vec![match group.kind {
+          GroupKind::CaptureName { ref name, .. } => Step::BeginNamedCapture(name.name.clone()),
+          _ => Step::BeginCapture,
+        }];
+        steps.extend(self.sequence(items(&group.ast)));
+        steps.push(Step::EndCapture);
+        steps
+      },
+      GroupKind::NonCapturing(ref flags) if flags.items.is_empty() => {
+        if let Some(step) = combinator(self.text(&group.span)).or_else(|| self.padded(group)) {
+          return vec![step];
+        }
+        match *group.ast {
+          // `capture` renders its group inside a non-capturing one.
+          Ast::Group(ref inner) if is_numbered(inner) => vec![Step::Capture(self.fragment(&inner.ast))],
+          Ast::Alternation(ref alternation) => vec![self.alternation(alternation)],
+          ref ast if literal_value(ast).is_some() => vec![Step::Then(self.text(ast.span()).to_string())],
+          ref ast => vec![Step::Concat(self.fragment(ast))],
+        }
+      },
+      GroupKind::NonCapturing(_) => vec![Step::FromRegex(self.text(&group.span).to_string())],
+    }
+  }
+
+  /// The `padded` step that rendered `group`, if one did.
+  fn padded(&self, group: &ast::Group) -> Option<Step> {
+    let asts = items(&group.ast);
+    let whitespace = |ast: &Ast| self.text(ast.span()) == "(?:\\s*)";
+    if asts.len() < 2 || !whitespace(&asts[0]) || !whitespace(&asts[asts.len() - 1]) {
+      return None;
+    }
+    Some(Step::Padded(self.anchored(&asts[1..asts.len() - 1])))
+      .filter(|step| Verex::from_steps(slice::from_ref(step)).as_string() == self.text(&group.span))
+  }
+
+  fn repetition(&self, repetition: &ast::Repetition) -> Vec<Step> {
+    let lazy = if repetition.greedy { None } else { Some(Step::Lazy) };
+    if let (RepetitionKind::ZeroOrOne, Ast::Group(group)) = (&repetition.op.kind, &*repetition.ast) {
+      if is_plain(group) && literal_value(&group.ast).is_some() {
+        return Some(Step::Maybe(self.text(group.ast.span()).to_string())).into_iter().chain(lazy).collect();
+      }
+    }
+    let quantifier = match repetition.op.kind {
+      RepetitionKind::ZeroOrOne => Step::CountRange(0, 1),
+      RepetitionKind::ZeroOrMore => Step::ZeroOrMore,
+      RepetitionKind::OneOrMore => Step::OneOrMore,
+      RepetitionKind::Range(RepetitionRange::Exactly(n)) => Step::Count(n as i32),
+      RepetitionKind::Range(RepetitionRange::AtLeast(n)) => Step::AtLeast(n as i32),
+      RepetitionKind::Range(RepetitionRange::Bounded(min, max)) => Step::CountRange(min as i32, max as i32),
+    };
+    let steps = self.node(&repetition.ast);
+    let bare: Vec<Step> = steps.iter().cloned().chain(Some(quantifier.clone())).chain(lazy.clone()).collect();
+    if Verex::from_steps(&bare).as_string() == self.text(&repetition.span) {
+      return bare;
+    }
+    // The quantifier applied to the last token only, so what is not a single token is grouped.
+    vec![Step::Concat(steps), quantifier].into_iter().chain(lazy).collect()
+  }
+
+  fn alternation(&self, alternation: &ast::Alternation) -> Step {
+    Step::Alternation(alternation.asts.iter().fold(Alternation::new(), |alternatives, ast| match literal_value(ast) {
+      // A literal alternative is escaped as `regex::escape` does, which `\/` for one is not.
+      Some(ref value) if regex::escape(value) == self.text(ast.span()) => alternatives.literal(value),
+      _ => alternatives.fragment(Verex::from_steps(&self.fragment(ast))),
+    }))
+  }
+}
+
+/// The items of a concatenation, or `ast` as the only one.
+fn items(ast: &Ast) -> &[Ast] {
+  match *ast {
+    Ast::Concat(ref concat) => &concat.asts,
+    ref ast => slice::from_ref(ast),
+  }
+}
+
+fn is_literal(ast: &Ast) -> bool {
+  matches!(*ast, Ast::Literal(_))
+}
+
+/// The text that `ast` matches if it is nothing but literals.
+fn literal_value(ast: &Ast) -> Option<String> {
+  let asts = items(ast);
+  if asts.is_empty() || !asts.iter().all(is_literal) {
+    return None;
+  }
+  Some(asts.iter().filter_map(|ast| match *ast {
+    Ast::Literal(ref literal) => Some(literal.c),
+    _ => None,
+  }).collect())
+}
+
+/// How many `^` start `asts` and how many `$` end them, with the items in between.
+fn anchors(asts: &[Ast]) -> (usize, &[Ast], usize) {
+  let starts = asts.iter().take_while(|ast| is_assertion(ast, AssertionKind::StartLine)).count();
+  let ends = asts[starts..].iter().rev().take_while(|ast| is_assertion(ast, AssertionKind::EndLine)).count();
+  (starts, &asts[starts..asts.len() - ends], ends)
+}
+
+fn is_assertion(ast: &Ast, kind: AssertionKind) -> bool {
+  match *ast {
+    Ast::Assertion(ref assertion) => assertion.kind == kind,
+    _ => false,
+  }
+}
+
+fn is_numbered(group: &ast::Group) -> bool {
+  matches!(group.kind, GroupKind::CaptureIndex(_))
+}
+
+fn is_plain(group: &ast::Group) -> bool {
+  match group.kind {
+    GroupKind::NonCapturing(ref flags) => flags.items.is_empty(),
+    _ => false,
+  }
+}
+
+/// The steps that set and clear `flags`.
+fn flag_steps(flags: &ast::Flags) -> Vec<Step> {
+  let (mut set, mut cleared, mut negated) = (String::new(), String::new(), false);
+  for item in &flags.items {
+    let letter = match item.kind {
+      FlagsItemKind::Negation => {
+        negated = true;
+        continue;
+      },
+      FlagsItemKind::Flag(Flag::CaseInsensitive) => 'i',
+      FlagsItemKind::Flag(Flag::MultiLine) => 'm',
+      FlagsItemKind::Flag(Flag::DotMatchesNewLine) => 's',
+      FlagsItemKind::Flag(Flag::SwapGreed) => 'U',
+      FlagsItemKind::Flag(Flag::Unicode) => 'u',
+      FlagsItemKind::Flag(Flag::CRLF) => 'R',
+      FlagsItemKind::Flag(Flag::IgnoreWhitespace) => 'x',
+    };
+    if negated { cleared.push(letter) } else { set.push(letter) }
+  }
+  match (&set[..], &cleared[..]) {
+    ("i", "") => vec![Step::WithAnyCase],
+    ("", "i") => vec![Step::WithSpecificCase],
+    _ => {
+      let mut steps = Vec::new();
+      if !set.is_empty() {
+        steps.push(Step::AddModifier(set));
+      }
+      if !cleared.is_empty() {
+        steps.push(Step::RemoveModifier(cleared));
+      }
+      steps
+    },
+  }
+}
@@ -0,0 +1,991 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use builder::{Rep, Step, Verex, NO_MATCH};
+use escape::escape_class;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EmailStrictness {
+  /// Anything of the form `something@something.tld`.
+  Simple,
+  /// Dot-atom local parts and hostname-shaped domains, allowing Unicode letters for
+  /// internationalized addresses, with local parts of at most 64 characters and addresses of at
+  /// most 254. The lengths are checked with lookaheads, so this needs the `fancy` backend; the
+  /// default backend reports `VerexError::RequiresFancyBackend`, since bounded repetitions of
+  /// Unicode classes would exceed its size limit.
+  Practical,
+  /// Closer to RFC 5322: ASCII only, with quoted local parts and domain literals.
+  Strict,
+}
+
+/// Selects what `Verex::uuid` accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UuidOpts {
+  /// Require this version nibble, such as `Some(4)`.
+  pub version: Option<u8>,
+  /// Require the RFC 4122 variant, `8`, `9`, `a` or `b` at the start of the fourth group.
+  pub rfc4122_variant: bool,
+  /// Accept uppercase hex digits as well as lowercase ones.
+  pub any_case: bool,
+  /// Accept the `{...}` form. The braces are not checked to be balanced.
+  pub braced: bool,
+  /// Accept the `urn:uuid:` form.
+  pub urn: bool,
+}
+
+impl Default for UuidOpts {
+  fn default() -> UuidOpts {
+    UuidOpts { version: None, rfc4122_variant: false, any_case: true, braced: false, urn: false }
+  }
+}
+
+/// Whether `Verex::url` requires, allows or leaves out a component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UrlPart {
+  Required,
+  Optional,
+  Absent,
+}
+
+impl UrlPart {
+  fn apply(self, v: Verex, f: fn(Verex) -> Verex) -> Verex {
+    match self {
+      UrlPart::Required => f(v),
+      UrlPart::Optional => f(v).quantify("?"),
+      UrlPart::Absent => v,
+    }
+  }
+}
+
+/// Selects the components of `Verex::url`. The scheme and host are always required.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UrlOpts {
+  pub userinfo: UrlPart,
+  pub port: UrlPart,
+  pub path: UrlPart,
+  pub query: UrlPart,
+  pub fragment: UrlPart,
+}
+
+impl Default for UrlOpts {
+  fn default() -> UrlOpts {
+    UrlOpts {
+      userinfo: UrlPart::Optional,
+      port: UrlPart::Optional,
+      path: UrlPart::Optional,
+      query: UrlPart::Optional,
+      fragment: UrlPart::Optional,
+    }
+  }
+}
+
+/// What separates the latitude from the longitude in `Verex::coordinates`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CoordSeparator {
+  /// A comma, with optional whitespace around it.
+  Comma,
+  Whitespace,
+  CommaOrWhitespace,
+}
+
+/// Selects what `Verex::coordinates` accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CoordOpts {
+  pub separator: CoordSeparator,
+  /// Expect unsigned degrees, each followed by an optional degree sign and a hemisphere letter,
+  /// as in `35.6895° N, 139.6917° E`, instead of signed decimal degrees.
+  pub hemisphere: bool,
+}
+
+impl Default for CoordOpts {
+  fn default() -> CoordOpts {
+    CoordOpts { separator: CoordSeparator::CommaOrWhitespace, hemisphere: false }
+  }
+}
+
+/// Selects what `Verex::hostname` and `Verex::domain_name` accept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HostnameOpts {
+  /// Accept a fully qualified name ending in a dot, such as `example.com.`.
+  pub trailing_dot: bool,
+  /// Accept Unicode letters and digits in labels, as in internationalized names that have not
+  /// been converted to `xn--` form. Such labels are not checked for length.
+  pub idn: bool,
+}
+
+/// The letters `Verex::roman_numeral` accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RomanCase {
+  Upper,
+  Lower,
+  /// Either case, even mixed within a numeral.
+  Any,
+}
+
+/// The registration plate formats `Verex::license_plate` knows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum PlateRegion {
+  /// Three letters and three or four digits, such as `ABC 1234` or `ABC-123`, which many US
+  /// states issue; vanity and state-specific formats are not covered.
+  UsGeneric,
+  /// The UK format since 2001, such as `AB51 ABC`: a two-letter area code, a two-digit age
+  /// identifier and three random letters.
+  Uk,
+  /// German plates such as `M-AB 1234` or `B AB 123E`: a district code of one to three letters,
+  /// one or two letters, and one to four digits not starting with 0, with an optional `E` for
+  /// electric or `H` for historic vehicles.
+  De,
+  /// Japanese plates such as `品川 300 さ 12-34`: a region name, a classification number, one
+  /// hiragana and a serial number, which is padded with `・` below 1000, as in `・・12`.
+  Jp,
+}
+
+/// The languages whose identifiers `Verex::identifier` knows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum IdentLang {
+  /// A letter or `_` followed by letters, digits and `_`, by the Unicode `XID_Start` and
+  /// `XID_Continue` properties, or ASCII only under `ascii_only`. A lone `_` is not an
+  /// identifier.
+  Rust,
+  /// ASCII letters, digits and `_`, not starting with a digit.
+  C,
+  /// Like `Rust`, by the `ID_Start` and `ID_Continue` properties, and with `$` allowed anywhere,
+  /// such as `$el` or `jQuery$`.
+  Js,
+  /// An XML 1.0 `Name`, which may also contain `:`, `-`, `.`, `·` and combining marks, such as
+  /// `xs:element` or `xml-stylesheet`.
+  XmlName,
+}
+
+/// One level of indentation for `Verex::indented`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IndentUnit {
+  Spaces(usize),
+  Tab,
+}
+
+impl IndentUnit {
+  pub(crate) fn pattern(self) -> String {
+    match self {
+      IndentUnit::Spaces(n) => format!(" {{{}}}", n),
+      IndentUnit::Tab => r"\t".to_string(),
+    }
+  }
+}
+
+/// The separators of a number for `Verex::decimal_with`, such as `1,234.56` in the US or
+/// `1.234,56` in much of Europe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NumberFormat {
+  /// Separates groups of three digits in the integer part. With a separator, numbers may also be
+  /// written without any.
+  pub thousands: Option<char>,
+  pub decimal: char,
+}
+
+/// The order of the fields of a date for `Verex::date_with`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DateOrder {
+  DMY,
+  MDY,
+  YMD,
+}
+
+/// The layout of a numeric date for `Verex::date_with`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DateFormat {
+  pub order: DateOrder,
+  pub sep: char,
+  /// Expect a four-digit year rather than a two-digit one.
+  pub four_digit_year: bool,
+}
+
+/// The requirements checked by `Verex::password_policy`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PasswordPolicy {
+  pub min_len: usize,
+  pub max_len: Option<usize>,
+  /// Require an uppercase letter.
+  pub require_upper: bool,
+  /// Require a lowercase letter.
+  pub require_lower: bool,
+  /// Require an ASCII digit.
+  pub require_digit: bool,
+  /// Require a character that is neither a letter, a digit nor whitespace.
+  pub require_special: bool,
+  /// Characters the password must not contain.
+  pub forbidden_chars: String,
+}
+
+impl Default for PasswordPolicy {
+  fn default() -> PasswordPolicy {
+    PasswordPolicy {
+      min_len: 8,
+      max_len: None,
+      require_upper: false,
+      require_lower: false,
+      require_digit: false,
+      require_special: false,
+      forbidden_chars: String::new(),
+    }
+  }
+}
+
+/// Selects how `Verex::wordlist` builds its alternation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WordlistOpts {
+  /// Factor common prefixes out of the words, as in `foo(?:bar|baz)`, instead of listing every
+  /// word in full.
+  pub trie: bool,
+  /// Only match whole words.
+  pub word_boundaries: bool,
+}
+
+impl Default for WordlistOpts {
+  fn default() -> WordlistOpts {
+    WordlistOpts { trie: true, word_boundaries: false }
+  }
+}
+
+#[derive(Default)]
+struct Trie {
+  end: bool,
+  children: BTreeMap<char, Trie>,
+}
+
+impl Trie {
+  fn insert(&mut self, word: &str) {
+    let node = word.chars().fold(self, |node, c| node.children.entry(c).or_default());
+    node.end = true;
+  }
+
+  fn pattern(&self) -> String {
+    let mut branches: Vec<String> = self.children.iter()
+      .map(|(c, child)| regex::escape(&c.to_string()) + &child.pattern())
+      .collect();
+    match (branches.len(), self.end) {
+      (0, _) => String::new(),
+      (1, false) => branches.remove(0),
+      (_, end) => format!("(?:{}){}", branches.join("|"), if end { "?" } else { "" }),
+    }
+  }
+}
+
+const DECIMAL: &str = r"[0-9]+(?:\.[0-9]+)?";
+
+pub(crate) const QUOTED_STRING: &str = r#"(?:"(?:[^"\\]|\\.|"")*")"#;
+
+const JSON_STRING: &str = r#"(?:"((?:[^"\\\x00-\x1f]|\\["\\/bfnrt]|\\u[0-9A-Fa-f]{4})*)")"#;
+/// The thousands, hundreds, tens and units of a roman numeral, each of which may be empty, and
+/// the same digits when they may not.
+const ROMAN_DIGITS: [(&str, &str); 4] = [
+  ("M{0,3}", "M{1,3}"),
+  ("(?:CM|CD|D?C{0,3})", "(?:CM|CD|DC{0,3}|C{1,3})"),
+  ("(?:XC|XL|L?X{0,3})", "(?:XC|XL|LX{0,3}|X{1,3})"),
+  ("(?:IX|IV|V?I{0,3})", "(?:IX|IV|VI{0,3}|I{1,3})"),
+];
+
+/// Ordinals by the suffix their number takes: `th` for numbers ending in 11, 12 and 13, and
+/// otherwise by the last digit.
+const ORDINAL: &str = r"(?:\b(?:[0-9]*1[0-9]th|(?:[0-9]*[02-9])?(?:1st|2nd|3rd|[04-9]th))\b)";
+
+const JSON_NUMBER: &str = r"(?:-?(?:0|[1-9][0-9]*)(?:\.[0-9]+)?(?:[eE][+-]?[0-9]+)?)";
+
+const CSS_NUMBER: &str = r"(?:[0-9]+(?:\.[0-9]*)?|\.[0-9]+)";
+
+const LATITUDE: &str = r"90(?:\.0+)?|[1-8]?[0-9](?:\.[0-9]+)?";
+const LONGITUDE: &str = r"180(?:\.0+)?|(?:1[0-7][0-9]|[1-9]?[0-9])(?:\.[0-9]+)?";
+
+const DURATION_VALUE: &str = r"[0-9]+(?:\.[0-9]+)?";
+const DURATION_UNIT: &str = "ns|us|\u{b5}s|\u{3bc}s|ms|s|m|h";
+
+const SIGIL_BODY: &str = r"[\p{L}\p{M}\p{N}_]+";
+
+fn grouped_digits(group: usize, sep: char) -> String {
+  assert!(group > 0, "digit group size must be at least 1");
+  format!("[0-9]{{1,{0}}}(?:{1}[0-9]{{{0}}})*", group, regex::escape(&sep.to_string()))
+}
+
+/// The parts of an ISBN between the prefix and the check character: the registration group,
+/// registrant and publication separated by hyphens or spaces, or nine bare digits.
+fn isbn_body(named: bool) -> String {
+  let part = |name: &str, digits: &str| {
+    if named { format!("(?P<{}>[0-9]{{{}}})", name, digits) } else { format!("[0-9]{{{}}}", digits) }
+  };
+  format!("(?:{}[- ]{}[- ]{}[- ]|[0-9]{{9}})", part("group", "1,5"), part("registrant", "1,7"), part("publication", "1,7"))
+}
+
+fn isbn_pattern() -> String {
+  format!(r"(?:\b(?:(?P<isbn13>97[89][- ]?{0}[0-9])|(?P<isbn10>{0}[0-9X]))\b)", isbn_body(false))
+}
+
+/// A DOI with the character set Crossref recommends for matching, which covers nearly all
+/// registered DOIs. The suffix must end with a letter or digit, so that punctuation after a
+/// DOI in running text is left out.
+const DOI: &str = r"(?:\b(?P<prefix>10\.[0-9]{4,9}(?:\.[0-9]+)*)/(?P<suffix>[-._;()/:A-Za-z0-9]*[A-Za-z0-9]))";
+
+/// A new-style arXiv identifier, `YYMM.NNNNN`, or an old-style one, `archive.SC/YYMMNNN`, with
+/// an optional version.
+const ARXIV_ID: &str = concat!(
+  r"(?:\b(?P<arxiv_id>[0-9]{2}(?:0[1-9]|1[0-2])\.[0-9]{4,5}|[a-z]+(?:-[a-z]+)*(?:\.[A-Z]{2})?/[0-9]{2}(?:0[1-9]|1[0-2])[0-9]{3})",
+  r"(?:v(?P<version>[1-9][0-9]*))?\b)",
+);
+
+/// The characters an XML 1.0 `Name` may start with, besides `:`.
+const XML_NAME_START: &str = concat!(
+  r"A-Za-z_\x{C0}-\x{D6}\x{D8}-\x{F6}\x{F8}-\x{2FF}\x{370}-\x{37D}\x{37F}-\x{1FFF}\x{200C}-\x{200D}",
+  r"\x{2070}-\x{218F}\x{2C00}-\x{2FEF}\x{3001}-\x{D7FF}\x{F900}-\x{FDCF}\x{FDF0}-\x{FFFD}\x{10000}-\x{EFFFF}",
+);
+
+/// The characters an XML 1.0 `Name` may continue with, besides `XML_NAME_START`.
+const XML_NAME_CHAR: &str = r":.0-9\x{B7}\x{300}-\x{36F}\x{203F}-\x{2040}-";
+
+/// One `/`-separated component of a git ref name: no control characters, spaces or any of
+/// `~^:?*[\`, dots only singly between other characters, and no `.lock` at the end.
+fn git_ref_component() -> String {
+  let char_but = |c: &str| format!(r"[^\x00-\x20\x7f~^:?*\[\\/.{}]", c);
+  let any = char_but("");
+  // The last dot-separated piece of a dotted component, which may be anything but `lock`.
+  let last = format!(
+    "(?:{0}{4}*|l(?:{1}{4}*)?|lo(?:{2}{4}*)?|loc(?:{3}{4}*)?|lock{4}+)",
+    char_but("l"), char_but("o"), char_but("c"), char_but("k"), any
+  );
+  format!(r"{0}+(?:(?:\.{0}+)*\.{1})?", any, last)
+}
+
+const HOST_LABEL: &str = "[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?";
+const HOST_LABEL_IDN: &str = r"[\p{L}\p{M}\p{N}](?:[\p{L}\p{M}\p{N}-]*[\p{L}\p{M}\p{N}])?";
+const HOST_TLD: &str = "[A-Za-z]{2,63}|xn--[A-Za-z0-9-]{1,59}";
+const HOST_TLD_IDN: &str = r"\p{L}[\p{L}\p{M}]+";
+
+const URL_PCHAR: &str = r"(?:[A-Za-z0-9._~!$&'()*+,;=:@-]|%[0-9A-Fa-f]{2})";
+const URL_TEXT: &str = r"(?:[A-Za-z0-9._~!$&'()*+,;=:@/?-]|%[0-9A-Fa-f]{2})";
+
+impl Verex {
+  pub fn email(self, strictness: EmailStrictness) -> Verex {
+    let (local, domain) = match strictness {
+      EmailStrictness::Simple => (r"[^\s@]+", r"[^\s@]+\.[^\s@]+"),
+      EmailStrictness::Practical => (
+        r"[\p{L}\p{N}!#$%&'*+/=?^_`{|}~-]+(?:\.[\p{L}\p{N}!#$%&'*+/=?^_`{|}~-]+)*",
+        r"(?:[\p{L}\p{N}](?:[\p{L}\p{N}-]*[\p{L}\p{N}])?\.)+(?:\p{L}{2,}|xn--[A-Za-z0-9-]+)",
+      ),
+      EmailStrictness::Strict => (
+        r#"(?:[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]+)*|"(?:[\x20\x21\x23-\x5B\x5D-\x7E]|\\[\x20-\x7E])*")"#,
+        r"(?:[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?)*|\[[\x21-\x5A\x5E-\x7E]*\])",
+      ),
+    };
+
+    self.record(Step::Email(strictness), |v| match strictness {
+      EmailStrictness::Practical => {
+        // The parts are atomic so that an address that is too long is not retried at every
+        // shorter length its domain could have.
+        v.length_checked(&format!("(?=[^@]{{1,64}}@)(?P<local>(?>{}))@(?P<domain>(?>{}))", local, domain), 3, 254)
+      },
+      _ => v.atom(&format!("(?:(?P<local>{})@(?P<domain>{}))", local, domain)),
+    })
+  }
+
+  /// Matches a URL scheme and the following `://`, capturing the name as `scheme`.
+  pub fn scheme(self) -> Verex {
+    self.record(Step::Scheme, |v| v.atom(r"(?:(?P<scheme>[A-Za-z][A-Za-z0-9+.-]*)://)"))
+  }
+
+  /// Matches `user:pass@`, capturing everything before the `@` as `userinfo`.
+  pub fn userinfo(self) -> Verex {
+    self.record(Step::Userinfo, |v| {
+      v.atom(r"(?:(?P<userinfo>(?:[A-Za-z0-9._~!$&'()*+,;=:-]|%[0-9A-Fa-f]{2})+)@)")
+    })
+  }
+
+  /// Matches a hostname, an IPv4 address or a bracketed IPv6 address, capturing it as `host`.
+  ///
+  /// Internationalized domain names are only matched in their punycode (`xn--`) form.
+  pub fn host(self) -> Verex {
+    self.record(Step::Host, |v| {
+      v.atom(r"(?P<host>\[[0-9A-Fa-f:.]+\]|[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?)*)")
+    })
+  }
+
+  /// Matches `:` followed by a port number, capturing the number as `port`.
+  pub fn port(self) -> Verex {
+    self.record(Step::Port, |v| v.atom(r"(?::(?P<port>[0-9]{1,5}))"))
+  }
+
+  /// Matches one or more `/`-separated segments, capturing them as `path`. Percent escapes must
+  /// be well formed.
+  pub fn path(self) -> Verex {
+    self.record(Step::Path, |v| v.atom(&format!("(?P<path>(?:/{}*)+)", URL_PCHAR)))
+  }
+
+  /// Matches `?` followed by a query string, capturing the string as `query`.
+  pub fn query(self) -> Verex {
+    self.record(Step::Query, |v| v.atom(&format!(r"(?:\?(?P<query>{}*))", URL_TEXT)))
+  }
+
+  /// Matches `#` followed by a fragment, capturing the fragment as `fragment`.
+  pub fn fragment(self) -> Verex {
+    self.record(Step::Fragment, |v| v.atom(&format!("(?:#(?P<fragment>{}*))", URL_TEXT)))
+  }
+
+  /// Composes the URL components, in order, as selected by `opts`.
+  pub fn url(self, opts: UrlOpts) -> Verex {
+    self.record(Step::Url(opts), |v| {
+      let v = opts.userinfo.apply(v.scheme(), Verex::userinfo).host();
+      let v = opts.port.apply(v, Verex::port);
+      let v = opts.path.apply(v, Verex::path);
+      let v = opts.query.apply(v, Verex::query);
+      opts.fragment.apply(v, Verex::fragment)
+    })
+  }
+
+  /// Matches a UTC offset such as `+09:00`, `-0330` or `Z`, capturing `sign`, `hours` and
+  /// `minutes`, or `utc` for `Z`.
+  pub fn timezone_offset(self) -> Verex {
+    self.record(Step::TimezoneOffset, |v| {
+      v.atom("(?:(?P<utc>Z)|(?P<sign>[+-])(?P<hours>[01][0-9]|2[0-3]):?(?P<minutes>[0-5][0-9]))")
+    })
+  }
+
+  /// Matches a Go-style duration such as `1h30m`, `250ms` or `-1.5s`.
+  pub fn duration(self) -> Verex {
+    self.record(Step::Duration, |v| {
+      v.atom(&format!("(?:[-+]?(?:(?:{}(?:{}))+|0))", DURATION_VALUE, DURATION_UNIT))
+    })
+  }
+
+  /// Matches a single value and unit of a duration, capturing them as `value` and `unit`. Use
+  /// with `CompiledVerex::captures_iter` to walk the parts of a duration.
+  pub fn duration_component(self) -> Verex {
+    self.record(Step::DurationComponent, |v| {
+      v.atom(&format!("(?:(?P<value>{})(?P<unit>{}))", DURATION_VALUE, DURATION_UNIT))
+    })
+  }
+
+  /// Matches an extended-format ISO 8601 week date such as `2024-W07-3` or `2024-W07`, capturing
+  /// `year`, `week` and `weekday`. Whether week 53 exists in the given year is not checked.
+  pub fn iso_week_date(self) -> Verex {
+    self.record(Step::IsoWeekDate, |v| {
+      v.atom("(?:(?P<year>[0-9]{4})-W(?P<week>0[1-9]|[1-4][0-9]|5[0-3])(?:-(?P<weekday>[1-7]))?)")
+    })
+  }
+
+  /// Matches a `#tag` that is not glued to a preceding word, capturing the body as `tag`. Tags
+  /// made of digits only, such as `#123`, are not matched; use `word_like('#')` to accept them.
+  pub fn hashtag(self) -> Verex {
+    self.record(Step::Hashtag, |v| v.atom(r"(?:\B#(?P<tag>[\p{N}_]*[\p{L}\p{M}][\p{L}\p{M}\p{N}_]*))"))
+  }
+
+  /// Matches an `@mention` that is not glued to a preceding word, so addresses like
+  /// `user@example.com` are not matched. The body is captured as `user`.
+  pub fn mention(self) -> Verex {
+    self.record(Step::Mention, |v| v.atom(&format!(r"(?:\B@(?P<user>{}))", SIGIL_BODY)))
+  }
+
+  /// Matches `prefix` followed by letters, digits or underscores, capturing them as `body`. Like
+  /// `hashtag` and `mention`, the prefix must not be glued to a preceding word.
+  pub fn word_like(self, prefix: char) -> Verex {
+    self.record(Step::WordLike(prefix), |v| {
+      v.atom(&format!(r"(?:\B{}(?P<body>{}))", regex::escape(&prefix.to_string()), SIGIL_BODY))
+    })
+  }
+
+  /// Matches a number written in groups of `group` digits separated by `sep`, such as
+  /// `1,234,567`, capturing it as `number`. The leading group may be shorter. A number short
+  /// enough to need no separator, such as `123`, is matched too.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `group` is zero.
+  pub fn digit_grouped(self, group: usize, sep: char) -> Verex {
+    self.record(Step::DigitGrouped(group, sep), |v| v.atom(&format!("(?P<number>{})", grouped_digits(group, sep))))
+  }
+
+  /// Like `digit_grouped`, but also accepts the digits without any separators, such as `1234567`.
+  pub fn digit_grouped_or_plain(self, group: usize, sep: char) -> Verex {
+    self.record(Step::DigitGroupedOrPlain(group, sep), |v| {
+      v.atom(&format!("(?P<number>{}|[0-9]+)", grouped_digits(group, sep)))
+    })
+  }
+
+  /// Matches a textual UUID as selected by `opts`, capturing the groups as `time_low`,
+  /// `time_mid`, `time_hi_and_version`, `clock_seq` and `node`, and the version nibble as
+  /// `version`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `opts.version` is not a single hex digit.
+  pub fn uuid(self, opts: UuidOpts) -> Verex {
+    let hex = if opts.any_case { "[0-9a-fA-F]" } else { "[0-9a-f]" };
+    let version = match opts.version {
+      Some(version) => {
+        assert!(version < 16, "UUID version {} is not a single hex digit", version);
+        let digit = format!("{:x}", version);
+        if opts.any_case { format!("[{}{}]", digit, digit.to_uppercase()) } else { digit }
+      },
+      None => hex.to_string(),
+    };
+    let variant = match (opts.rfc4122_variant, opts.any_case) {
+      (true, true) => "[89abAB]",
+      (true, false) => "[89ab]",
+      (false, _) => hex,
+    };
+    let core = format!(
+      "(?P<time_low>{0}{{8}})-(?P<time_mid>{0}{{4}})-(?P<time_hi_and_version>(?P<version>{1}){0}{{3}})-(?P<clock_seq>{2}{0}{{3}})-(?P<node>{0}{{12}})",
+      hex, version, variant
+    );
+    let (open, close) = match (opts.braced, opts.urn) {
+      (false, false) => ("", ""),
+      (true, false) => ("\\{?", "\\}?"),
+      (false, true) => ("(?:(?i:urn:uuid:))?", ""),
+      (true, true) => ("(?:\\{|(?i:urn:uuid:))?", "\\}?"),
+    };
+
+    self.record(Step::Uuid(opts), |v| v.atom(&format!("(?:{}{}{})", open, core, close)))
+  }
+
+  /// Matches a comment that starts with `prefix` and runs to the end of the line. The line
+  /// terminator is not part of the match; use `line_comment_with_newline` to consume it too.
+  ///
+  /// This is a naive matcher: it knows nothing about string literals, so a `prefix` inside a
+  /// quoted string, as in `"// not a comment"`, starts a comment as well.
+  pub fn line_comment(self, prefix: &str) -> Verex {
+    self.record(Step::LineComment(prefix.to_string()), |v| v.atom(&format!(r"(?:{}[^\r\n]*)", regex::escape(prefix))))
+  }
+
+  /// Like `line_comment`, but also matches the `\n` or `\r\n` that ends the line, if any.
+  pub fn line_comment_with_newline(self, prefix: &str) -> Verex {
+    self.record(Step::LineCommentWithNewline(prefix.to_string()), |v| {
+      v.atom(&format!(r"(?:{}[^\r\n]*(?:\r?\n)?)", regex::escape(prefix)))
+    })
+  }
+
+  /// Matches a comment from `open` to the nearest `close`, across line breaks. Comments do not
+  /// nest, and like `line_comment` the markers are also found inside string literals.
+  pub fn block_comment(self, open: &str, close: &str) -> Verex {
+    self.record(Step::BlockComment(open.to_string(), close.to_string()), |v| {
+      v.atom(&format!("(?:{}(?s:.*?){})", regex::escape(open), regex::escape(close)))
+    })
+  }
+
+  /// Matches the structure of an ISBN-10, compact like `030640615X` or with the parts separated
+  /// by hyphens or spaces like `0-306-40615-2`. The check character, a digit or an uppercase
+  /// `X`, is captured as `check`, and the separated parts as `group`, `registrant` and
+  /// `publication`. The check character is not verified, nor that the separated parts add up to
+  /// ten characters.
+  ///
+  /// The number must not continue a longer run of digits, but since a hyphen is not part of a
+  /// word, the tail of a hyphenated ISBN-13 looks like an ISBN-10; use `isbn` to prefer the
+  /// longer form.
+  pub fn isbn10(self) -> Verex {
+    self.record(Step::Isbn10, |v| v.atom(&format!(r"(?:\b{}(?P<check>[0-9X])\b)", isbn_body(true))))
+  }
+
+  /// Matches the structure of an ISBN-13 such as `978-0-306-40615-7` or `9780306406157`,
+  /// capturing the `978` or `979` as `prefix` and the rest like `isbn10`. The check digit is not
+  /// verified.
+  pub fn isbn13(self) -> Verex {
+    self.record(Step::Isbn13, |v| v.atom(&format!(r"(?:\b(?P<prefix>97[89])(?:[- ]?){}(?P<check>[0-9])\b)", isbn_body(true))))
+  }
+
+  /// Matches an ISBN-13 or an ISBN-10, preferring the former. The regex crate does not allow a
+  /// group name in both branches of an alternation, so only the whole number is captured, as
+  /// `isbn13` or `isbn10`.
+  pub fn isbn(self) -> Verex {
+    self.record(Step::Isbn, |v| v.atom(&isbn_pattern()))
+  }
+
+  /// Matches a DOI such as `10.1000/182` or `10.1016/S0140-6736(20)30183-5`, also within a
+  /// `https://doi.org/` link, capturing the registrant code, such as `10.1000`, as `prefix` and
+  /// the rest as `suffix`.
+  pub fn doi(self) -> Verex {
+    self.record(Step::Doi, |v| v.atom(DOI))
+  }
+
+  /// Matches an arXiv identifier, new-style such as `2101.00001v2` or old-style such as
+  /// `math.GT/0309136`, capturing it without the version as `arxiv_id`, and the version number,
+  /// if any, as `version`.
+  pub fn arxiv_id(self) -> Verex {
+    self.record(Step::ArxivId, |v| v.atom(ARXIV_ID))
+  }
+
+  /// Matches a DOI, an arXiv identifier or an ISBN, preferring them in that order, with the
+  /// captures of the `doi`, `arxiv_id` and `isbn` methods; which of `prefix`, `arxiv_id`,
+  /// `isbn13` and `isbn10` took part tells which was found.
+  pub fn citation(self) -> Verex {
+    self.record(Step::Citation, |v| v.atom(&format!("(?:{}|{}|{})", DOI, ARXIV_ID, isbn_pattern())))
+  }
+
+  /// Matches a compact EAN-13 barcode number, capturing the three-digit GS1 prefix as `prefix`,
+  /// the company and item number as `item` and the check digit as `check`, which is not verified.
+  pub fn ean13(self) -> Verex {
+    self.record(Step::Ean13, |v| v.atom(r"(?:\b(?P<prefix>[0-9]{3})(?P<item>[0-9]{9})(?P<check>[0-9])\b)"))
+  }
+
+  /// Matches a latitude/longitude pair in decimal degrees, such as `35.6895, 139.6917` or
+  /// `-90.0 180.0`, capturing the numbers as `lat` and `lon`. Latitudes beyond 90 and
+  /// longitudes beyond 180 are rejected by the structure of the pattern. With
+  /// `opts.hemisphere`, the hemisphere letters are captured as `lat_hemisphere` and
+  /// `lon_hemisphere`.
+  pub fn coordinates(self, opts: CoordOpts) -> Verex {
+    let separator = match opts.separator {
+      CoordSeparator::Comma => r"\s*,\s*",
+      CoordSeparator::Whitespace => r"\s+",
+      CoordSeparator::CommaOrWhitespace => r"\s*,\s*|\s+",
+    };
+    let pattern = if opts.hemisphere {
+      format!(
+        r"(?:\b(?P<lat>{})\s*°?\s*(?P<lat_hemisphere>[NS])(?:{})\b(?P<lon>{})\s*°?\s*(?P<lon_hemisphere>[EW])\b)",
+        LATITUDE, separator, LONGITUDE
+      )
+    } else {
+      format!(r"(?:(?P<lat>[-+]?\b(?:{}))(?:{})(?P<lon>[-+]?\b(?:{}))\b)", LATITUDE, separator, LONGITUDE)
+    };
+    self.record(Step::Coordinates(opts), |v| v.atom(&pattern))
+  }
+
+  /// Matches a whole line that satisfies `policy`, checking each required kind of character with
+  /// `require_somewhere`. Like it, the pattern needs the `fancy` backend.
+  pub fn password_policy(self, policy: &PasswordPolicy) -> Verex {
+    self.record(Step::PasswordPolicy(policy.clone()), |v| {
+      let requirements = [
+        (policy.require_upper, r"\p{Lu}"),
+        (policy.require_lower, r"\p{Ll}"),
+        (policy.require_digit, "[0-9]"),
+        (policy.require_special, r"[^\p{L}\p{N}\s]"),
+      ];
+      let v = requirements.iter()
+        .filter(|&&(required, _)| required)
+        .fold(v.atom("^"), |v, &(_, class)| v.require_somewhere(|r| r.atom(class)));
+      let allowed = if policy.forbidden_chars.is_empty() {
+        ".".to_string()
+      } else {
+        format!("[^{}\\n]", escape_class(&policy.forbidden_chars))
+      };
+      let length = match policy.max_len {
+        Some(max) => format!("{{{},{}}}", policy.min_len, max),
+        None => format!("{{{},}}", policy.min_len),
+      };
+      v.atom(&allowed).quantify(&length).atom("$")
+    })
+  }
+
+  /// Matches a percentage such as `45%`, `12.5 %` or `100%`, capturing the number as `value`.
+  pub fn percent(self) -> Verex {
+    self.record(Step::Percent, |v| v.atom(&format!(r"(?:\b(?P<value>{}) ?%)", DECIMAL)))
+  }
+
+  /// Matches a ratio of two integers such as `3:2`, capturing them as `left` and `right`.
+  pub fn ratio(self) -> Verex {
+    self.record(Step::Ratio, |v| v.atom(r"(?:\b(?P<left>[0-9]+):(?P<right>[0-9]+)\b)"))
+  }
+
+  /// Matches two numbers separated by one of `seps`, such as `10-20` or `1.5..2.5`, capturing
+  /// them as `low` and `high`. A single space is allowed on each side of the separator. Whether
+  /// `low` is actually the lower number is not checked.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `seps` is empty or contains an empty separator.
+  pub fn numeric_range(self, seps: &[&str]) -> Verex {
+    assert!(!seps.is_empty() && seps.iter().all(|sep| !sep.is_empty()), "numeric_range needs non-empty separators");
+    let alternatives = seps.iter().map(|sep| regex::escape(sep)).collect::<Vec<_>>().join("|");
+    self.record(Step::NumericRange(seps.iter().map(|sep| sep.to_string()).collect()), |v| {
+      v.atom(&format!(r"(?:\b(?P<low>{0}) ?(?:{1}) ?(?P<high>{0})\b)", DECIMAL, alternatives))
+    })
+  }
+
+  /// Matches any of `words` literally. A word that is a prefix of another, like `foo` of
+  /// `foobar`, never cuts the longer one short. Empty words are ignored; with no words left,
+  /// nothing matches.
+  pub fn wordlist(self, words: &[&str], opts: WordlistOpts) -> Verex {
+    let step = Step::Wordlist(words.iter().map(|word| word.to_string()).collect(), opts);
+    let mut words: Vec<&str> = words.iter().cloned().filter(|word| !word.is_empty()).collect();
+    let alternation = if words.is_empty() {
+      NO_MATCH.to_string()
+    } else if opts.trie {
+      let mut trie = Trie::default();
+      for word in &words {
+        trie.insert(word);
+      }
+      trie.pattern()
+    } else {
+      words.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+      words.dedup();
+      words.iter().map(|word| regex::escape(word)).collect::<Vec<_>>().join("|")
+    };
+    let boundary = if opts.word_boundaries { r"\b" } else { "" };
+
+    self.record(step, |v| v.atom(&format!("(?:{0}(?:{1}){0})", boundary, alternation)))
+  }
+
+  /// Matches a CSS color: `#rgb` or `#rrggbb`, captured as `hex`; `rgb(...)` or `rgba(...)`,
+  /// capturing `red`, `green`, `blue` and the optional `alpha`; or `hsl(...)`, capturing `hue`,
+  /// `saturation` and `lightness`. Hex digits and function names match in any case, without
+  /// making the rest of the pattern case-insensitive. Component values are not range-checked.
+  pub fn css_color(self) -> Verex {
+    let channel = format!("{}%?", CSS_NUMBER);
+    let pattern = format!(
+      concat!(
+        r"(?i:#(?P<hex>[0-9a-f]{{6}}|[0-9a-f]{{3}})\b",
+        r"|rgba?\(\s*(?P<red>{1})\s*,\s*(?P<green>{1})\s*,\s*(?P<blue>{1})\s*(?:,\s*(?P<alpha>{1})\s*)?\)",
+        r"|hsl\(\s*(?P<hue>{0}(?:deg)?)\s*,\s*(?P<saturation>{0}%)\s*,\s*(?P<lightness>{0}%)\s*\))",
+      ),
+      CSS_NUMBER, channel
+    );
+    self.record(Step::CssColor, |v| v.atom(&pattern))
+  }
+
+  /// Matches an HTML character reference: a named one like `&amp;`, capturing `name`, or a
+  /// numeric one like `&#169;` or `&#x2764;`, capturing `decimal` or `hexadecimal`. Names are not
+  /// checked against the list of defined entities.
+  pub fn html_entity(self) -> Verex {
+    self.record(Step::HtmlEntity, |v| {
+      v.atom(r"(?:&(?:(?P<name>[A-Za-z][A-Za-z0-9]*)|#(?P<decimal>[0-9]+)|#(?i:x(?P<hexadecimal>[0-9a-f]+)));)")
+    })
+  }
+
+  /// Matches one percent-encoded byte such as `%2F` or `%2f`, capturing the hex digits as `byte`.
+  pub fn percent_encoded(self) -> Verex {
+    self.record(Step::PercentEncoded, |v| v.atom(r"(?:%(?P<byte>(?i:[0-9a-f]{2})))"))
+  }
+
+  /// Matches a run of consecutive percent-encoded bytes, such as `%E2%9C%93`.
+  pub fn percent_encoded_run(self) -> Verex {
+    self.record(Step::PercentEncodedRun, |v| v.atom(r"(?:(?:%(?i:[0-9a-f]{2}))+)"))
+  }
+
+  /// Matches a double-quoted string, quotes included. Inside, a quote is escaped either with a
+  /// backslash or by doubling it, as in CSV.
+  pub fn quoted_string(self) -> Verex {
+    self.record(Step::QuotedString, |v| v.atom(QUOTED_STRING))
+  }
+
+  /// Matches a JSON string literal, quotes included, and captures its content between the quotes
+  /// in an unnamed group, escapes left as they are. Only the escapes JSON allows are accepted:
+  /// `\"`, `\\`, `\/`, `\b`, `\f`, `\n`, `\r`, `\t` and `\u` with four hex digits.
+  pub fn json_string(self) -> Verex {
+    self.record(Step::JsonString, |v| v.atom(JSON_STRING))
+  }
+
+  /// Matches a JSON number: an optional minus sign, an integer part without leading zeros, and
+  /// an optional fraction and exponent. Like the other presets it is not anchored, so in `01`
+  /// it matches the `0`; anchor it or follow it with a delimiter to reject such text.
+  pub fn json_number(self) -> Verex {
+    self.record(Step::JsonNumber, |v| v.atom(JSON_NUMBER))
+  }
+
+  /// Matches a roman numeral from 1 to 3999 in standard subtractive notation, such as `IV` or
+  /// `MCMXCIV`, as a whole word, capturing it as `numeral`. Forms such as `IIII`, `VV` and `IC`
+  /// are rejected.
+  pub fn roman_numeral(self, case: RomanCase) -> Verex {
+    // Some digit must be present: the first one that is starts the numeral.
+    let alternatives: Vec<String> = (0..ROMAN_DIGITS.len()).map(|first| {
+      let mut alternative = ROMAN_DIGITS[first].1.to_string();
+      for &(digit, _) in &ROMAN_DIGITS[first + 1..] {
+        alternative.push_str(digit);
+      }
+      alternative
+    }).collect();
+    let numeral = alternatives.join("|");
+    let numeral = match case {
+      RomanCase::Upper => numeral,
+      RomanCase::Lower => numeral.to_lowercase(),
+      RomanCase::Any => format!("(?i:{})", numeral),
+    };
+    self.record(Step::RomanNumeral(case), |v| v.atom(&format!(r"(?:\b(?P<numeral>{})\b)", numeral)))
+  }
+
+  /// Matches an English ordinal written with digits, such as `1st`, `22nd`, `3rd` or `11th`, as a
+  /// whole word. The suffix must agree with the number, so `11st` and `3th` are rejected.
+  ///
+  /// Nothing is captured: agreeing with the suffix splits the number into alternatives, and
+  /// one group cannot span the number in all of them. The number is the match without its
+  /// last two characters.
+  pub fn ordinal(self) -> Verex {
+    self.record(Step::Ordinal, |v| v.atom(ORDINAL))
+  }
+
+  /// Matches a vehicle registration plate laid out as in `region`, with a named capture for each
+  /// segment; see `PlateRegion` for the segments. Only the structure is checked: letters must be
+  /// uppercase and the digits in range, but whether the codes and series have been issued is
+  /// not known. Segments may be separated by a space, or the separator the region uses.
+  pub fn license_plate(self, region: PlateRegion) -> Verex {
+    let pattern = match region {
+      PlateRegion::UsGeneric => r"(?:\b(?P<letters>[A-Z]{3})[- ]?(?P<digits>[0-9]{3,4})\b)",
+      PlateRegion::Uk => r"(?:\b(?P<area>[A-Z]{2})(?P<age>[0-9]{2}) ?(?P<serial>[A-Z]{3})\b)",
+      PlateRegion::De => {
+        r"(?:\b(?P<district>[A-ZÄÖÜ]{1,3})[- ](?P<letters>[A-Z]{1,2}) ?(?P<digits>[1-9][0-9]{0,3})(?P<suffix>[EH])?\b)"
+      },
+      PlateRegion::Jp => concat!(
+        r"(?:(?P<region>[\p{Han}\p{Hiragana}]{1,5}) ?(?P<class>[0-9]{1,3}) ?(?P<kana>\p{Hiragana}) ?",
+        r"(?P<number>[0-9]{2}-[0-9]{2}|・[1-9][0-9]{2}|・・[1-9][0-9]|・・・[1-9]))",
+      ),
+    };
+    self.record(Step::LicensePlate(region), |v| v.atom(pattern))
+  }
+
+  /// Matches an identifier of `lang`; see `IdentLang` for what each allows. The match starts at
+  /// a word boundary, so it is never the tail of a longer identifier, and takes as many
+  /// identifier characters as there are. A `$` or `:` that starts an identifier only needs no
+  /// word character before it.
+  pub fn identifier(self, lang: IdentLang) -> Verex {
+    self.record(Step::Identifier(lang), |v| {
+      let pattern = match lang {
+        IdentLang::Rust => v.class(
+          r"\b(?:[A-Za-z][A-Za-z0-9_]*|_[A-Za-z0-9_]+)\b",
+          r"\b(?:\p{XID_Start}\p{XID_Continue}*|_\p{XID_Continue}+)\b",
+        ),
+        IdentLang::C => r"(?:\b[A-Za-z_][A-Za-z0-9_]*\b)".to_string(),
+        IdentLang::Js => v.class(
+          r"(?:\b[A-Za-z_]|\B\$)[A-Za-z0-9_$]*",
+          r"(?:\b[\p{ID_Start}_]|\B\$)[\p{ID_Continue}$\x{200C}\x{200D}]*",
+        ),
+        IdentLang::XmlName => v.class(
+          r"(?:\b[A-Za-z_]|\B:)[A-Za-z0-9_:.-]*",
+          &format!(r"(?:\b[{0}]|\B:)[{0}{1}]*", XML_NAME_START, XML_NAME_CHAR),
+        ),
+      };
+      v.atom(&pattern)
+    })
+  }
+
+  /// Matches an XML 1.0 name, such as an element or attribute name; the same as
+  /// `identifier(IdentLang::XmlName)`.
+  pub fn xml_name(self) -> Verex {
+    self.record(Step::XmlName, |v| v.identifier(IdentLang::XmlName))
+  }
+
+  /// Matches from the start of a line up to the `n`th field, counting from 1, and captures that
+  /// field, like `awk '{ print $n }'`. Fields are separated by `sep`, and may be empty; with
+  /// `None`, they are separated by runs of spaces and tabs, and leading blanks are skipped.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `n` is 0.
+  pub fn column<S: Into<Option<char>>>(self, n: usize, sep: S) -> Verex {
+    let sep = sep.into();
+    self.record(Step::Column(n, sep), |v| v.columns(n..=n, sep))
+  }
+
+  /// Like `column`, but captures the fields from `range.start()` to `range.end()` together,
+  /// separators included.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the range is empty or starts at 0.
+  pub fn columns<S: Into<Option<char>>>(self, range: RangeInclusive<usize>, sep: S) -> Verex {
+    let (first, last) = (*range.start(), *range.end());
+    assert!(first >= 1 && first <= last, "invalid column range {}..={}", first, last);
+    let sep = sep.into();
+    let (lead, field, sep_pattern) = match sep {
+      Some(sep) => (String::new(), format!(r"[^{}\n]*", escape_class(&sep.to_string())), regex::escape(&sep.to_string())),
+      None => (r"[ \t]*".to_string(), r"[^ \t\n]+".to_string(), r"[ \t]+".to_string()),
+    };
+    let pattern = format!(
+      "(?:^{lead}(?:{field}{sep}){{{skip}}}((?:{field}{sep}){{{span}}}{field}))",
+      lead = lead, field = field, sep = sep_pattern, skip = first - 1, span = last - first
+    );
+    self.record(Step::Columns(first, last, sep), |v| v.atom(&pattern))
+  }
+
+  /// Matches a git commit hash of lowercase hex digits, with as many digits as `rep` allows, as a
+  /// whole word. `Rep::Between(7, 40)` accepts both abbreviated and full SHA-1 hashes.
+  pub fn commit_sha(self, rep: Rep) -> Verex {
+    self.record(Step::CommitSha(rep), |v| v.atom(&format!(r"(?:\b[0-9a-f]{}\b)", rep.quantifier())))
+  }
+
+  /// Matches a fully qualified branch or tag ref, such as `refs/heads/main` or `refs/tags/v1.0`,
+  /// capturing `heads` or `tags` as `kind` and the rest, checked as by `branch_name`, as `name`.
+  pub fn git_ref(self) -> Verex {
+    let pattern = format!("(?:refs/(?P<kind>heads|tags)/(?P<name>{0}(?:/{0})*))", git_ref_component());
+    self.record(Step::GitRef, |v| v.atom(&pattern))
+  }
+
+  /// Matches a line that is a branch name following git's ref name rules as far as they can be
+  /// checked without lookaround: no control characters, spaces or any of `~^:?*[\`, no empty
+  /// components, and no component that starts or ends with a dot, ends with `.lock` or contains
+  /// `..`. Names containing `@{` are not rejected. The name is anchored to the start and end of
+  /// the line, since without lookaround a valid prefix such as `feature` in `feature..x` could
+  /// not be told apart from a whole name.
+  pub fn branch_name(self) -> Verex {
+    let pattern = format!("(?:^{0}(?:/{0})*$)", git_ref_component());
+    self.record(Step::BranchName, |v| v.atom(&pattern))
+  }
+
+  /// Matches a line that is a hostname: dot-separated labels of 1 to 63 letters, digits and
+  /// hyphens, none starting or ending with a hyphen. `xn--` labels are accepted like any other.
+  /// The name without its trailing dot is captured as `labels`. The 253-byte limit on the whole
+  /// name is not checked. Like `branch_name`, the name is anchored to the start and end of the
+  /// line, so that `-bad.example` or `a..b` give no match at all rather than a part of them.
+  pub fn hostname(self, opts: HostnameOpts) -> Verex {
+    let label = if opts.idn { HOST_LABEL_IDN } else { HOST_LABEL };
+    let pattern = format!(r"(?:^(?P<labels>{0}(?:\.{0})*){1}$)", label, if opts.trailing_dot { r"\.?" } else { "" });
+    self.record(Step::Hostname(opts), |v| v.atom(&pattern))
+  }
+
+  /// Like `hostname`, but requires at least two labels, the last of which must look like a top
+  /// level domain: letters only, or an `xn--` label. The last label is also captured as `tld`.
+  pub fn domain_name(self, opts: HostnameOpts) -> Verex {
+    let (label, tld) = if opts.idn {
+      (HOST_LABEL_IDN, format!("{}|{}", HOST_TLD, HOST_TLD_IDN))
+    } else {
+      (HOST_LABEL, HOST_TLD.to_string())
+    };
+    let pattern = format!(
+      r"(?:(?P<labels>(?:{0}\.)+(?P<tld>{1})){2})",
+      label, tld, if opts.trailing_dot { r"\.?" } else { "" }
+    );
+    self.record(Step::DomainName(opts), |v| v.atom(&pattern))
+  }
+
+  /// Matches a decimal number written with the separators of `format`, capturing the optional
+  /// `sign`, the `integer` part with any thousands separators, and the optional `fraction`.
+  pub fn decimal_with(self, format: NumberFormat) -> Verex {
+    let integer = match format.thousands {
+      Some(sep) => format!("[0-9]{{1,3}}(?:{}[0-9]{{3}})+|[0-9]+", regex::escape(&sep.to_string())),
+      None => "[0-9]+".to_string(),
+    };
+    let pattern = format!(
+      r"(?:(?P<sign>[-+])?(?P<integer>{})(?:{}(?P<fraction>[0-9]+))?)",
+      integer, regex::escape(&format.decimal.to_string())
+    );
+    self.record(Step::DecimalWith(format), |v| v.atom(&pattern))
+  }
+
+  /// Matches a numeric date laid out as in `format`, capturing `year`, `month` and `day`. Days
+  /// and months take exactly two digits, from 01 to 31 and from 01 to 12; whether the day exists
+  /// in that month is not checked.
+  pub fn date_with(self, format: DateFormat) -> Verex {
+    let year = if format.four_digit_year { "(?P<year>[0-9]{4})" } else { "(?P<year>[0-9]{2})" };
+    let month = "(?P<month>0[1-9]|1[0-2])";
+    let day = "(?P<day>0[1-9]|[12][0-9]|3[01])";
+    let fields = match format.order {
+      DateOrder::DMY => [day, month, year],
+      DateOrder::MDY => [month, day, year],
+      DateOrder::YMD => [year, month, day],
+    };
+    let pattern = format!(r"(?:\b{}\b)", fields.join(&regex::escape(&format.sep.to_string())));
+    self.record(Step::DateWith(format), |v| v.atom(&pattern))
+  }
+}
@@ -0,0 +1,97 @@
+use std::ops::Range;
+use regex::RegexSet;
+
+use builder::Verex;
+use compiled::CompiledVerex;
+use error::VerexError;
+
+/// Matches text against several named patterns at once.
+///
+/// A `RegexSet` of all the patterns first finds which of them match at all; only those are then
+/// run on their own to extract captures. When most patterns do not match most inputs, this is
+/// much cheaper than running every pattern.
+#[derive(Clone, Debug)]
+pub struct VerexScanner {
+  set: RegexSet,
+  patterns: Vec<(String, CompiledVerex)>,
+}
+
+impl VerexScanner {
+  pub fn new(patterns: Vec<(String, Verex)>) -> Result<VerexScanner, VerexError> {
+    let patterns = patterns.into_iter()
+      .map(|(name, verex)| verex.compile().map(|compiled| (name, compiled)))
+      .collect::<Result<Vec<_>, _>>()?;
+    let sources: Vec<&str> = patterns.iter().map(|(_, compiled)| compiled.as_str()).collect();
+    let set = RegexSet::new(&sources).map_err(|error| VerexError::Compile { pattern: sources.join("\n"), error })?;
+    Ok(VerexScanner { set, patterns })
+  }
+
+  /// Whether any of the patterns matches `text`.
+  pub fn is_match(&self, text: &str) -> bool {
+    self.set.is_match(text)
+  }
+
+  /// Removes the strings that match any of the patterns from `texts`.
+  pub fn retain_not_matching(&self, texts: &mut Vec<String>) {
+    texts.retain(|text| !self.set.is_match(text));
+  }
+
+  /// Splits `texts` into those that match any of the patterns and those that match none.
+  pub fn partition_by_match<I, S>(&self, texts: I) -> (Vec<S>, Vec<S>) where I: IntoIterator<Item = S>, S: AsRef<str> {
+    texts.into_iter().partition(|text| self.set.is_match(text.as_ref()))
+  }
+
+  /// The names of the patterns that match `text`, in the order they were given.
+  pub fn matching(&self, text: &str) -> Vec<&str> {
+    self.set.matches(text).into_iter().map(|i| &self.patterns[i].0[..]).collect()
+  }
+
+  /// The captures of the first match of every pattern that matches `text`, by pattern name, in
+  /// the order the patterns were given.
+  pub fn scan(&self, text: &str) -> Vec<(&str, Vec<String>)> {
+    self.set.matches(text).into_iter()
+      .map(|i| {
+        let (ref name, ref compiled) = self.patterns[i];
+        (&name[..], compiled.captures(text))
+      })
+      .collect()
+  }
+  /// Labels the regions of `text` that the patterns match, in order of position. Where matches
+  /// of different patterns overlap, the longest is kept, and of equally long ones, that of the
+  /// pattern given first; a match that overlaps one already kept is dropped whole. Empty
+  /// matches are not labeled.
+  pub fn annotate(&self, text: &str) -> Vec<Annotation> {
+    let mut candidates: Vec<(usize, Range<usize>)> = Vec::new();
+    for i in self.set.matches(text).into_iter() {
+      let regex = self.patterns[i].1.regex();
+      candidates.extend(regex.find_iter(text).filter(|m| !m.is_empty()).map(|m| (i, m.range())));
+    }
+    candidates.sort_by_key(|&(i, ref range)| (usize::MAX - range.len(), i, range.start));
+
+    let mut kept: Vec<(usize, Range<usize>)> = Vec::new();
+    for (i, range) in candidates {
+      if kept.iter().all(|(_, other)| range.end <= other.start || other.end <= range.start) {
+        kept.push((i, range));
+      }
+    }
+    kept.sort_by_key(|(_, range)| range.start);
+    kept.into_iter()
+      .map(|(i, range)| Annotation { label: self.patterns[i].0.clone(), text: text[range.clone()].to_string(), range })
+      .collect()
+  }
+}
+
+/// A region of text labeled by `VerexScanner::annotate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Annotation {
+  pub label: String,
+  pub range: Range<usize>,
+  pub text: String,
+}
+
+/// Labels the regions of `text` that `patterns` match. See `VerexScanner::annotate`; build a
+/// scanner instead to annotate many texts with the same patterns.
+pub fn annotate(text: &str, patterns: &[(&str, Verex)]) -> Result<Vec<Annotation>, VerexError> {
+  let patterns = patterns.iter().map(|&(label, ref verex)| (label.to_string(), verex.clone())).collect();
+  Ok(VerexScanner::new(patterns)?.annotate(text))
+}
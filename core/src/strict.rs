@@ -0,0 +1,121 @@
+use builder::{Step, Verex};
+use compiled::CompiledVerex;
+use error::{validate_steps, VerexError};
+
+/// Builds a `Verex` from recorded steps, checking them before anything is generated.
+///
+/// The chain API on `Verex` accepts any sequence of calls and only reports problems when the
+/// pattern is compiled, if at all. `build` instead validates the steps first, requires every
+/// capture group to be closed, resolves the numbering of the groups as `duplicate_groups` says,
+/// rejecting a tag given to two groups, and normalizes the steps before applying them, so a
+/// pattern it returns is structurally sound. It also drops what `lints` reports, which the pattern does
+/// not need for unanchored searches.
+#[derive(Clone, Debug, Default)]
+pub struct VerexBuilder {
+  steps: Vec<Step>,
+}
+
+impl VerexBuilder {
+  pub fn new() -> VerexBuilder {
+    VerexBuilder { steps: Vec::new() }
+  }
+
+  /// Starts from the steps recorded by an existing chain.
+  pub fn from_verex(verex: &Verex) -> VerexBuilder {
+    VerexBuilder { steps: verex.steps().to_vec() }
+  }
+
+  pub fn step(mut self, step: Step) -> VerexBuilder {
+    self.steps.push(step);
+    self
+  }
+
+  pub fn steps<I: IntoIterator<Item = Step>>(mut self, steps: I) -> VerexBuilder {
+    self.steps.extend(steps);
+    self
+  }
+
+  /// Checks the steps for unbalanced groups, invalid modifiers and group names, and empty
+  /// alternatives. Errors carry the index of the offending step.
+  pub fn validate(&self) -> Result<(), VerexError> {
+    validate_steps(&self.steps, None)
+  }
+
+  /// The steps with runs of adjacent mode switches, such as `with_any_case` directly followed by
+  /// `with_specific_case`, reduced to the last switch of each run, which is the only one with an
+  /// effect.
+  pub fn normalized(&self) -> Vec<Step> {
+    let mut steps: Vec<Step> = Vec::new();
+    for step in &self.steps {
+      if let Some(last) = steps.last_mut() {
+        if same_switch(last, step) {
+          *last = step.clone();
+          continue;
+        }
+      }
+      steps.push(step.clone());
+    }
+    steps
+  }
+
+  /// The rewrites `build` makes that change the matches it finds, though not whether there is
+  /// one, each explained in a sentence.
+  pub fn lints(&self) -> Vec<String> {
+    Verex::from_steps(&self.steps).redundant_dotstar().into_iter().map(|i| format!(
+      "anything() at step {} is dropped: an unanchored search finds the rest of the pattern anywhere without it, and its matches cover only the rest",
+      i
+    )).collect()
+  }
+
+  pub fn build(&self) -> Result<Verex, VerexError> {
+    self.validate()?;
+    let verex = Verex::from_steps(&self.normalized()).strip_redundant_dotstar();
+    verex.check_closed()?;
+    verex.check_groups()?;
+    Ok(verex)
+  }
+
+  pub fn build_compiled(&self) -> Result<CompiledVerex, VerexError> {
+    self.build()?.compile()
+  }
+}
+
+impl Verex {
+  /// The pattern without the `anything` steps at its start and end, which an unanchored search
+  /// such as `is_match` or `find_iter` does not need: `.*x` matches wherever `x` does, and only
+  /// stretches the match to the start of the line. Matches then cover just the rest.
+  ///
+  /// An `anything` is kept when an anchor or flag is rendered next to it, as with
+  /// `start_of_line`, `starts_with` or `end_of_line`, when a later step quantifies it or wraps it
+  /// along with the rest, as `times` and `with_total_length` do, and when `unbounded` bounds or
+  /// forbids it. A pattern matched against the whole text should keep them too.
+  pub fn strip_redundant_dotstar(&self) -> Verex {
+    let redundant = self.redundant_dotstar();
+    if redundant.is_empty() {
+      return self.clone();
+    }
+    let steps: Vec<Step> = self.steps().iter().enumerate()
+      .filter(|&(i, _)| !redundant.contains(&i))
+      .map(|(_, step)| step.clone())
+      .collect();
+    Verex::from_steps(&steps)
+  }
+}
+
+fn same_switch(a: &Step, b: &Step) -> bool {
+  match (a, b) {
+    (&Step::WithAnyCase, &Step::WithAnyCase)
+    | (&Step::WithAnyCase, &Step::WithSpecificCase)
+    | (&Step::WithSpecificCase, &Step::WithAnyCase)
+    | (&Step::WithSpecificCase, &Step::WithSpecificCase)
+    | (&Step::SearchMultiline, &Step::SearchMultiline)
+    | (&Step::SearchMultiline, &Step::SearchOneline)
+    | (&Step::SearchOneline, &Step::SearchMultiline)
+    | (&Step::SearchOneline, &Step::SearchOneline) => true,
+    (&Step::SetFlag(x), &Step::SetFlag(y))
+    | (&Step::SetFlag(x), &Step::ClearFlag(y))
+    | (&Step::ClearFlag(x), &Step::SetFlag(y))
+    | (&Step::ClearFlag(x), &Step::ClearFlag(y)) => x == y,
+    _ => false,
+  }
+}
@@ -0,0 +1,423 @@
+//! Writes patterns in the syntax of other regex engines, so that a pattern built here can be
+//! handed to nginx, a database or a browser.
+
+use std::error::Error;
+use std::fmt;
+use regex_syntax;
+use regex_syntax::ast::parse::Parser;
+use regex_syntax::ast::{self, Assertion, AssertionKind, Ast, ClassAsciiKind, ClassPerlKind, ClassSet, ClassSetItem, ClassUnicodeKind, FlagsItemKind, GroupKind, LiteralKind, RepetitionKind, RepetitionRange, Span};
+use regex_syntax::hir::translate::Translator;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use builder::Verex;
+
+/// The general categories, by short and long name.
+const GENERAL_CATEGORIES: &[(&str, &str)] = &[
+  ("L", "Letter"), ("LC", "Cased_Letter"), ("Lu", "Uppercase_Letter"), ("Ll", "Lowercase_Letter"),
+  ("Lt", "Titlecase_Letter"), ("Lm", "Modifier_Letter"), ("Lo", "Other_Letter"),
+  ("M", "Mark"), ("Mn", "Nonspacing_Mark"), ("Mc", "Spacing_Mark"), ("Me", "Enclosing_Mark"),
+  ("N", "Number"), ("Nd", "Decimal_Number"), ("Nl", "Letter_Number"), ("No", "Other_Number"),
+  ("P", "Punctuation"), ("Pc", "Connector_Punctuation"), ("Pd", "Dash_Punctuation"), ("Ps", "Open_Punctuation"),
+  ("Pe", "Close_Punctuation"), ("Pi", "Initial_Punctuation"), ("Pf", "Final_Punctuation"), ("Po", "Other_Punctuation"),
+  ("S", "Symbol"), ("Sm", "Math_Symbol"), ("Sc", "Currency_Symbol"), ("Sk", "Modifier_Symbol"), ("So", "Other_Symbol"),
+  ("Z", "Separator"), ("Zs", "Space_Separator"), ("Zl", "Line_Separator"), ("Zp", "Paragraph_Separator"),
+  ("C", "Other"), ("Cc", "Control"), ("Cf", "Format"), ("Cs", "Surrogate"), ("Co", "Private_Use"), ("Cn", "Unassigned"),
+];
+
+/// The binary properties that both PCRE2 and JavaScript know, by the name they share and the
+/// aliases the `regex` crate accepts as well.
+const BINARY_PROPERTIES: &[(&str, &[&str])] = &[
+  ("ASCII", &[]), ("ASCII_Hex_Digit", &["AHex"]), ("Alphabetic", &["Alpha"]), ("Any", &[]), ("Assigned", &[]),
+  ("Bidi_Control", &["Bidi_C"]), ("Bidi_Mirrored", &["Bidi_M"]), ("Case_Ignorable", &["CI"]), ("Cased", &[]),
+  ("Dash", &[]), ("Default_Ignorable_Code_Point", &["DI"]), ("Deprecated", &["Dep"]), ("Diacritic", &["Dia"]),
+  ("Emoji", &[]), ("Emoji_Component", &["EComp"]), ("Emoji_Modifier", &["EMod"]), ("Emoji_Modifier_Base", &["EBase"]),
+  ("Emoji_Presentation", &["EPres"]), ("Extended_Pictographic", &["ExtPict"]), ("Extender", &["Ext"]),
+  ("Grapheme_Base", &["Gr_Base"]), ("Grapheme_Extend", &["Gr_Ext"]), ("Hex_Digit", &["Hex"]),
+  ("ID_Continue", &["IDC"]), ("ID_Start", &["IDS"]), ("Ideographic", &["Ideo"]), ("Join_Control", &["Join_C"]),
+  ("Lowercase", &["Lower"]), ("Math", &[]), ("Noncharacter_Code_Point", &["NChar"]), ("Pattern_Syntax", &["Pat_Syn"]),
+  ("Pattern_White_Space", &["Pat_WS"]), ("Quotation_Mark", &["QMark"]), ("Radical", &[]), ("Regional_Indicator", &["RI"]),
+  ("Sentence_Terminal", &["STerm"]), ("Soft_Dotted", &["SD"]), ("Terminal_Punctuation", &["Term"]),
+  ("Unified_Ideograph", &["UIdeo"]), ("Uppercase", &["Upper"]), ("Variation_Selector", &["VS"]),
+  ("White_Space", &["WSpace", "space"]), ("XID_Continue", &["XIDC"]), ("XID_Start", &["XIDS"]),
+];
+
+/// A regex syntax that `Verex::to_syntax` can write patterns in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Flavor {
+  /// The syntax of the `regex` crate, as `as_string` writes it.
+  Rust,
+  /// PCRE2, as used by nginx, PHP and `grep -P`, in UTF mode.
+  Pcre,
+  /// A JavaScript `RegExp` literal, such as `/^a+$/iu`, with the `u` flag always set.
+  EcmaScript,
+}
+
+/// A part of a pattern that `Verex::to_syntax` cannot write in `flavor`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedConstruct {
+  pub flavor: Flavor,
+  /// The part of the pattern, as the `regex` crate writes it.
+  pub construct: String,
+}
+
+impl fmt::Display for UnsupportedConstruct {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:?} cannot be written in {:?} syntax", self.construct, self.flavor)
+  }
+}
+
+impl Error for UnsupportedConstruct {}
+
+impl Verex {
+  /// The pattern in the syntax of `flavor`, written from the pattern that `as_string` gives.
+  /// Flag groups, group names and anchors are translated, so that `end_of_line` becomes `\z` for
+  /// PCRE, whose `$` also matches before a final line break, and inline flags at the start of a
+  /// JavaScript pattern become flags of the literal. Whitespace in literals is escaped, so the
+  /// `x` flag is left out.
+  ///
+  /// `\b` and the classes `\d`, `\s` and `\w` are written as they are, though PCRE and
+  /// JavaScript give them their ASCII meaning, and so is `.`, which JavaScript also stops at
+  /// `\r`. Lookarounds and atomic groups, which need
+  /// the `fancy` backend, non-ASCII bytes, and anything else the flavor has no syntax for, such
+  /// as flags in the middle of a JavaScript pattern, are reported as `UnsupportedConstruct`. A
+  /// pattern that does not compile at all is reported at the part that fails.
+  pub fn to_syntax(&self, flavor: Flavor) -> Result<String, UnsupportedConstruct> {
+    if let Some(construct) = self.backend_construct() {
+      return Err(UnsupportedConstruct { flavor, construct: construct.to_string() });
+    }
+    let pattern = self.clone().as_string();
+    let unsupported = |span: &Span| UnsupportedConstruct { flavor, construct: pattern[span.start.offset..span.end.offset].to_string() };
+    let ast = Parser::new().parse(&pattern).map_err(|err| unsupported(err.span()))?;
+    Translator::new().translate(&pattern, &ast).map_err(|err| unsupported(err.span()))?;
+
+    let mut writer = Writer { flavor, pattern: &pattern, modes: Modes::default() };
+    match flavor {
+      Flavor::Rust => Ok(pattern.clone()),
+      Flavor::Pcre => writer.ast(&ast),
+      Flavor::EcmaScript => writer.ecmascript(&ast),
+    }
+  }
+
+  /// The pattern in PCRE2 syntax; see `to_syntax`.
+  pub fn to_pcre_string(&self) -> Result<String, UnsupportedConstruct> {
+    self.to_syntax(Flavor::Pcre)
+  }
+
+  /// The pattern as a JavaScript `RegExp` literal; see `to_syntax`.
+  pub fn to_js_string(&self) -> Result<String, UnsupportedConstruct> {
+    self.to_syntax(Flavor::EcmaScript)
+  }
+}
+
+/// The flags in effect where a `Writer` is, which some constructs are written by.
+#[derive(Clone, Copy, Default)]
+struct Modes {
+  case_insensitive: bool,
+  multi_line: bool,
+  dot_matches_new_line: bool,
+  swap_greed: bool,
+}
+
+/// Writes a parsed pattern in a flavor other than `Flavor::Rust`.
+struct Writer<'p> {
+  flavor: Flavor,
+  pattern: &'p str,
+  modes: Modes,
+}
+
+impl<'p> Writer<'p> {
+  fn unsupported(&self, span: &Span) -> UnsupportedConstruct {
+    UnsupportedConstruct { flavor: self.flavor, construct: self.pattern[span.start.offset..span.end.offset].to_string() }
+  }
+
+  /// Writes a JavaScript literal. Flags set at the start of the pattern, before anything but
+  /// anchors, become the flags of the literal, and the anchors are written by them.
+  fn ecmascript(&mut self, ast: &Ast) -> Result<String, UnsupportedConstruct> {
+    let asts = match *ast {
+      Ast::Concat(ref concat) => &concat.asts[..],
+      ref ast => ::std::slice::from_ref(ast),
+    };
+    let mut anchors = Vec::new();
+    let mut rest = asts;
+    while let Some((first, tail)) = rest.split_first() {
+      match *first {
+        Ast::Flags(ref set) => {
+          let toggles_multi_line = set.flags.items.iter().any(|item| item.kind == FlagsItemKind::Flag(ast::Flag::MultiLine));
+          if toggles_multi_line && !anchors.is_empty() {
+            return Err(self.unsupported(&set.span));
+          }
+          self.flags(&set.flags, &set.span)?;
+        },
+        Ast::Assertion(ref assertion) if assertion.kind == AssertionKind::StartLine || assertion.kind == AssertionKind::StartText => {
+          anchors.push(first);
+        },
+        _ => break,
+      }
+      rest = tail;
+    }
+
+    let mut body = String::new();
+    for ast in anchors.into_iter().chain(rest) {
+      body += &self.ast(ast)?;
+    }
+    if body.is_empty() {
+      body.push_str("(?:)");
+    }
+    let flags: String = [(self.modes.case_insensitive, 'i'), (self.modes.multi_line, 'm'), (self.modes.dot_matches_new_line, 's')]
+      .iter()
+      .filter(|&&(set, _)| set)
+      .map(|&(_, letter)| letter)
+      .collect();
+    Ok(format!("/{}/{}u", body, flags))
+  }
+
+  fn ast(&mut self, ast: &Ast) -> Result<String, UnsupportedConstruct> {
+    Ok(match *ast {
+      Ast::Empty(_) => String::new(),
+      Ast::Flags(ref set) if self.flavor == Flavor::EcmaScript => return Err(self.unsupported(&set.span)),
+      Ast::Flags(ref set) => match self.flags(&set.flags, &set.span)? {
+        ref flags if flags.is_empty() => String::new(),
+        flags => format!("(?{})", flags),
+      },
+      Ast::Literal(ref literal) => self.literal(literal, false),
+      Ast::Dot(_) => ".".to_string(),
+      Ast::Assertion(ref assertion) => self.assertion(assertion)?.to_string(),
+      Ast::ClassUnicode(ref class) => self.unicode_class(class)?,
+      Ast::ClassPerl(ref class) => perl_class(class).to_string(),
+      Ast::ClassBracketed(ref class) => {
+        format!("[{}{}]", if class.negated { "^" } else { "" }, self.class_set(&class.kind)?)
+      },
+      Ast::Repetition(ref repetition) => {
+        let op = match repetition.op.kind {
+          RepetitionKind::ZeroOrOne => "?".to_string(),
+          RepetitionKind::ZeroOrMore => "*".to_string(),
+          RepetitionKind::OneOrMore => "+".to_string(),
+          RepetitionKind::Range(RepetitionRange::Exactly(n)) => format!("{{{}}}", n),
+          RepetitionKind::Range(RepetitionRange::AtLeast(n)) => format!("{{{},}}", n),
+          RepetitionKind::Range(RepetitionRange::Bounded(min, max)) => format!("{{{},{}}}", min, max),
+        };
+        // JavaScript has no `U` flag, so the laziness it swaps is written out.
+        let lazy = if self.flavor == Flavor::EcmaScript { repetition.greedy == self.modes.swap_greed } else { !repetition.greedy };
+        self.ast(&repetition.ast)? + &op + if lazy { "?" } else { "" }
+      },
+      Ast::Group(ref group) => {
+        let outer = self.modes;
+        let open = match group.kind {
+          GroupKind::CaptureIndex(_) => "(".to_string(),
+          GroupKind::CaptureName { ref name, .. } if name.name.chars().all(|c| c == '_' || c.is_ascii_alphanumeric()) => {
+            format!("(?<{}>", name.name)
+          },
+          GroupKind::CaptureName { ref name, .. } => return Err(self.unsupported(&name.span)),
+          GroupKind::NonCapturing(ref flags) if flags.items.is_empty() => "(?:".to_string(),
+          GroupKind::NonCapturing(_) if self.flavor == Flavor::EcmaScript => return Err(self.unsupported(&group.span)),
+          GroupKind::NonCapturing(ref flags) => format!("(?{}:", self.flags(flags, &group.span)?),
+        };
+        let inner = self.ast(&group.ast)?;
+        self.modes = outer;
+        open + &inner + ")"
+      },
+      Ast::Alternation(ref alternation) => {
+        let mut alternatives = Vec::new();
+        for ast in &alternation.asts {
+          alternatives.push(self.ast(ast)?);
+        }
+        alternatives.join("|")
+      },
+      Ast::Concat(ref concat) => {
+        let mut out = String::new();
+        for ast in &concat.asts {
+          out += &self.ast(ast)?;
+        }
+        out
+      },
+    })
+  }
+
+  /// Applies `flags`, and writes the ones to keep. `x` is left out, since whitespace is always
+  /// escaped, and so is `u`, since both flavors are written for Unicode text. A flag that
+  /// cannot be kept is reported as the construct at `span` that sets it.
+  fn flags(&mut self, flags: &ast::Flags, span: &Span) -> Result<String, UnsupportedConstruct> {
+    let mut written = String::new();
+    let mut enable = true;
+    for item in &flags.items {
+      let (mode, letter) = match item.kind {
+        FlagsItemKind::Negation => {
+          enable = false;
+          written.push('-');
+          continue;
+        },
+        FlagsItemKind::Flag(ast::Flag::CaseInsensitive) => (&mut self.modes.case_insensitive, 'i'),
+        FlagsItemKind::Flag(ast::Flag::MultiLine) => (&mut self.modes.multi_line, 'm'),
+        FlagsItemKind::Flag(ast::Flag::DotMatchesNewLine) => (&mut self.modes.dot_matches_new_line, 's'),
+        FlagsItemKind::Flag(ast::Flag::SwapGreed) => (&mut self.modes.swap_greed, 'U'),
+        FlagsItemKind::Flag(ast::Flag::IgnoreWhitespace) => continue,
+        FlagsItemKind::Flag(ast::Flag::Unicode) if enable => continue,
+        FlagsItemKind::Flag(_) => return Err(self.unsupported(span)),
+      };
+      *mode = enable;
+      written.push(letter);
+    }
+    Ok(written.trim_end_matches('-').to_string())
+  }
+
+  fn assertion(&self, assertion: &Assertion) -> Result<&'static str, UnsupportedConstruct> {
+    let multi_line = self.modes.multi_line;
+    Ok(match (self.flavor, &assertion.kind) {
+      (_, &AssertionKind::StartLine) => "^",
+      (Flavor::Pcre, &AssertionKind::EndLine) if !multi_line => r"\z",
+      (_, &AssertionKind::EndLine) => "$",
+      (Flavor::Pcre, &AssertionKind::StartText) => r"\A",
+      (Flavor::Pcre, &AssertionKind::EndText) => r"\z",
+      (Flavor::EcmaScript, &AssertionKind::StartText) if !multi_line => "^",
+      (Flavor::EcmaScript, &AssertionKind::EndText) if !multi_line => "$",
+      (_, &AssertionKind::WordBoundary) => r"\b",
+      (_, &AssertionKind::NotWordBoundary) => r"\B",
+      _ => return Err(self.unsupported(&assertion.span)),
+    })
+  }
+
+  /// `literal`, escaped if it is special in either flavor, and written as an escape if it was
+  /// one or is a control or whitespace character other than a space.
+  fn literal(&self, literal: &ast::Literal, in_class: bool) -> String {
+    let c = literal.c;
+    let special = if in_class { r"\[]^-" } else { r"\.+*?()|[]{}^$" };
+    let escaped = match literal.kind {
+      LiteralKind::Verbatim | LiteralKind::Meta | LiteralKind::Superfluous => c != ' ' && (c.is_control() || c.is_whitespace()),
+      _ => true,
+    };
+    if special.contains(c) || (c == '/' && self.flavor == Flavor::EcmaScript) {
+      format!(r"\{}", c)
+    } else if escaped {
+      match (c, self.flavor) {
+        ('\t', _) => r"\t".to_string(),
+        ('\n', _) => r"\n".to_string(),
+        ('\r', _) => r"\r".to_string(),
+        ('\x0C', _) => r"\f".to_string(),
+        (_, Flavor::EcmaScript) => format!(r"\u{{{:X}}}", u32::from(c)),
+        _ => format!(r"\x{{{:X}}}", u32::from(c)),
+      }
+    } else {
+      c.to_string()
+    }
+  }
+
+  /// A Unicode class, by the names both flavors share. Scripts are written as script
+  /// extensions, which is what a bare script name means to the `regex` crate.
+  fn unicode_class(&self, class: &ast::ClassUnicode) -> Result<String, UnsupportedConstruct> {
+    let property = match class.kind {
+      ClassUnicodeKind::OneLetter(c) => general_category(&c.to_string()).map(str::to_string),
+      ClassUnicodeKind::Named(ref name) => general_category(name)
+        .or_else(|| binary_property(name))
+        .map(str::to_string)
+        .or_else(|| script(name).map(|script| self.script_extensions(&script))),
+      ClassUnicodeKind::NamedValue { ref name, ref value, .. } => match &*loose(name) {
+        "gc" | "generalcategory" => general_category(value).map(str::to_string),
+        "scx" | "scriptextensions" => script(value).map(|script| self.script_extensions(&script)),
+        "sc" | "script" => script(value).map(|script| match self.flavor {
+          Flavor::EcmaScript => format!("Script={}", script),
+          _ => format!("sc:{}", script),
+        }),
+        _ => None,
+      },
+    };
+    let p = if class.is_negated() { 'P' } else { 'p' };
+    property.map(|property| format!(r"\{}{{{}}}", p, property)).ok_or_else(|| self.unsupported(&class.span))
+  }
+
+  fn script_extensions(&self, script: &str) -> String {
+    match self.flavor {
+      Flavor::EcmaScript => format!("Script_Extensions={}", script),
+      _ => script.to_string(),
+    }
+  }
+
+  fn class_set(&self, set: &ClassSet) -> Result<String, UnsupportedConstruct> {
+    match *set {
+      ClassSet::Item(ref item) => self.class_item(item),
+      ClassSet::BinaryOp(ref op) => Err(self.unsupported(&op.span)),
+    }
+  }
+
+  /// The items of a bracketed class. Nested classes are flattened into the class around them,
+  /// which only works for classes that are not negated.
+  fn class_item(&self, item: &ClassSetItem) -> Result<String, UnsupportedConstruct> {
+    Ok(match *item {
+      ClassSetItem::Empty(_) => String::new(),
+      ClassSetItem::Literal(ref literal) => self.literal(literal, true),
+      ClassSetItem::Range(ref range) => format!("{}-{}", self.literal(&range.start, true), self.literal(&range.end, true)),
+      ClassSetItem::Ascii(ref class) if !class.negated => ascii_class(&class.kind).to_string(),
+      ClassSetItem::Unicode(ref class) => self.unicode_class(class)?,
+      ClassSetItem::Perl(ref class) => perl_class(class).to_string(),
+      ClassSetItem::Bracketed(ref class) if !class.negated => self.class_set(&class.kind)?,
+      ClassSetItem::Union(ref union) => {
+        let mut out = String::new();
+        for item in &union.items {
+          out += &self.class_item(item)?;
+        }
+        out
+      },
+      ref item => return Err(self.unsupported(item.span())),
+    })
+  }
+}
+
+fn perl_class(class: &ast::ClassPerl) -> &'static str {
+  match (&class.kind, class.negated) {
+    (&ClassPerlKind::Digit, false) => r"\d",
+    (&ClassPerlKind::Digit, true) => r"\D",
+    (&ClassPerlKind::Space, false) => r"\s",
+    (&ClassPerlKind::Space, true) => r"\S",
+    (&ClassPerlKind::Word, false) => r"\w",
+    (&ClassPerlKind::Word, true) => r"\W",
+  }
+}
+
+/// The ranges of an ASCII class such as `[:alpha:]`, which JavaScript has no syntax for.
+fn ascii_class(kind: &ClassAsciiKind) -> &'static str {
+  match *kind {
+    ClassAsciiKind::Alnum => "0-9A-Za-z",
+    ClassAsciiKind::Alpha => "A-Za-z",
+    ClassAsciiKind::Ascii => r"\x00-\x7F",
+    ClassAsciiKind::Blank => r"\t ",
+    ClassAsciiKind::Cntrl => r"\x00-\x1F\x7F",
+    ClassAsciiKind::Digit => "0-9",
+    ClassAsciiKind::Graph => "!-~",
+    ClassAsciiKind::Lower => "a-z",
+    ClassAsciiKind::Print => " -~",
+    ClassAsciiKind::Punct => r"!-/:-@\[-`{-~",
+    ClassAsciiKind::Space => r"\t\n\x0B\f\r ",
+    ClassAsciiKind::Upper => "A-Z",
+    ClassAsciiKind::Word => "0-9A-Za-z_",
+    ClassAsciiKind::Xdigit => "0-9A-Fa-f",
+  }
+}
+
+/// `name` as the `regex` crate compares property names: ignoring case, spaces, `_` and `-`.
+fn loose(name: &str) -> String {
+  name.chars().filter(|&c| c != ' ' && c != '_' && c != '-').flat_map(char::to_lowercase).collect()
+}
+
+fn general_category(name: &str) -> Option<&'static str> {
+  let name = loose(name);
+  GENERAL_CATEGORIES.iter().find(|&&(short, long)| loose(short) == name || loose(long) == name).map(|&(short, _)| short)
+}
+
+fn binary_property(name: &str) -> Option<&'static str> {
+  let name = loose(name);
+  BINARY_PROPERTIES.iter()
+    .find(|&&(canonical, aliases)| loose(canonical) == name || aliases.iter().any(|&alias| loose(alias) == name))
+    .map(|&(canonical, _)| canonical)
+}
+
+/// `name` as a script name with each word capitalized, such as `Old_Italic`, if it names a
+/// script.
+fn script(name: &str) -> Option<String> {
+  regex_syntax::parse(&format!(r"\p{{scx={}}}", name)).ok()?;
+  let words: Vec<String> = name.split([' ', '_', '-']).filter(|word| !word.is_empty()).map(|word| {
+    let mut chars = word.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+  }).collect();
+  Some(words.join("_"))
+}
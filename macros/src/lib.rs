@@ -0,0 +1,565 @@
+//! `verex_static!`, re-exported by `verbal_expressions` with the `macros` feature.
+//!
+//! Patterns are built and checked with `verbal_expressions_core`, the builder that
+//! `verbal_expressions` re-exports, while the macro is expanded.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+extern crate verbal_expressions_core;
+
+use std::cell::Cell;
+use std::str::FromStr;
+
+use proc_macro::TokenStream;
+use syn::spanned::Spanned;
+use syn::{Error, Expr, Lit, RangeLimits, UnOp};
+
+use verbal_expressions_core::builder::{DuplicateGroups, Flag, Rep, Step, Unbounded, Verex};
+use verbal_expressions_core::presets::{CoordOpts, CoordSeparator, DateFormat, DateOrder, EmailStrictness, HostnameOpts, IdentLang, IndentUnit, NumberFormat, PasswordPolicy, PlateRegion, RomanCase, UrlOpts, UrlPart, UuidOpts, WordlistOpts};
+
+/// Builder methods whose arguments are closures or other builders, which cannot be evaluated
+/// while the macro is expanded.
+const UNSUPPORTED: &[&str] = &["alternation", "atomic", "between_lines", "capture", "concat", "define", "delimited_record", "length_bounded", "padded", "repeat_fragment", "require_somewhere"];
+
+/// A builder call in the macro input.
+struct Call {
+  method: syn::Ident,
+  args: Vec<Expr>,
+}
+
+/// Builds a pattern from a chain of builder calls, such as
+/// `verex_static!(start_of_line().then("v").digit().one_or_more())`, and checks at compile time
+/// that it compiles. The expansion is a `&'static CompiledVerex` that is compiled on first use.
+///
+/// Arguments must be literals, or paths, calls and struct expressions of literals such as
+/// `Rep::Exactly(2)`; methods that take closures, such as `capture`, are not supported. A
+/// pattern that fails to compile is reported at the call that caused it when that is known, and
+/// at the whole input otherwise.
+#[proc_macro]
+pub fn verex_static(input: TokenStream) -> TokenStream {
+  match syn::parse::<Expr>(input).and_then(|input| expand(&input)) {
+    Ok(tokens) => tokens.into(),
+    Err(err) => compile_error(&err).into(),
+  }
+}
+
+/// Like `Error::to_compile_error`, but without a `::core` path, which 2015 edition crates lack.
+fn compile_error(err: &Error) -> proc_macro2::TokenStream {
+  let message = err.to_string();
+  quote_spanned!(err.span()=> compile_error!(#message))
+}
+
+fn expand(input: &Expr) -> Result<proc_macro2::TokenStream, Error> {
+  let calls = calls(input)?;
+  let mut steps = Vec::new();
+  for call in &calls {
+    steps.push(step(call)?);
+  }
+
+  let verex = Verex::from_steps(&steps);
+  if let Err(err) = verex.compile() {
+    let span = err.step().and_then(|i| calls.get(i)).map_or(input.span(), |call| call.method.span());
+    return Err(Error::new(span, format!("invalid pattern: {}", err)));
+  }
+
+  let methods = calls.iter().map(|call| &call.method);
+  let args = calls.iter().map(|call| &call.args);
+  Ok(quote! {{
+    static VEREX: ::std::sync::OnceLock<::verbal_expressions::CompiledVerex> = ::std::sync::OnceLock::new();
+    VEREX.get_or_init(|| {
+      ::verbal_expressions::Verex::new()
+        #(.#methods(#(#args),*))*
+        .compile()
+        .expect("verex_static! checks the pattern at compile time")
+    })
+  }})
+}
+
+/// Flattens a chain of method calls into calls in order, starting from a plain call.
+fn calls(expr: &Expr) -> Result<Vec<Call>, Error> {
+  match *expr {
+    Expr::MethodCall(ref call) => {
+      let mut calls = calls(&call.receiver)?;
+      calls.push(Call { method: call.method.clone(), args: call.args.iter().cloned().collect() });
+      Ok(calls)
+    },
+    Expr::Call(ref call) => match *call.func {
+      Expr::Path(ref path) if path.path.get_ident().is_some() => {
+        let method = path.path.get_ident().cloned().expect("checked above");
+        Ok(vec![Call { method, args: call.args.iter().cloned().collect() }])
+      },
+      _ => Err(Error::new(call.func.span(), "expected a builder method name")),
+    },
+    _ => Err(Error::new(expr.span(), "expected a chain of builder calls, such as `start_of_line().then(\"a\")`")),
+  }
+}
+
+/// The `Step` that a call records, as `Step::apply` would replay it.
+fn step(call: &Call) -> Result<Step, Error> {
+  let method = call.method.to_string();
+  if UNSUPPORTED.contains(&&method[..]) {
+    let message = format!("`{}` takes a closure or another pattern, which verex_static! cannot evaluate", method);
+    return Err(Error::new(call.method.span(), message));
+  }
+  let args = Args::new(call.method.span(), &call.args);
+  let step = match &method[..] {
+      "start_of_line" => Step::StartOfLine,
+      "end_of_line" => Step::EndOfLine,
+      "pop" => Step::Pop,
+      "pattern" => Step::FromRegex(args.get(0)?),
+      "find" => Step::Find(args.get(0)?),
+      "raw" => Step::Raw(args.get(0)?),
+      "then" => Step::Then(args.get(0)?),
+      "maybe" => Step::Maybe(args.get(0)?),
+      "then_any_case" => Step::ThenAnyCase(args.get(0)?),
+      "then_exact_case" => Step::ThenExactCase(args.get(0)?),
+      "then_casefold" => Step::ThenCasefold(args.get(0)?),
+      "starts_with" => Step::StartsWith(args.get(0)?),
+      "ends_with" => Step::EndsWith(args.get(0)?),
+      "anything" => Step::Anything,
+      "anything_but_not" => Step::AnythingButNot(args.get(0)?),
+      "anything_but" => Step::AnythingBut(args.get(0)?),
+      "something" => Step::Something,
+      "something_but_not" => Step::SomethingButNot(args.get(0)?),
+      "something_but" => Step::SomethingBut(args.get(0)?),
+      "until" => Step::Until(args.get(0)?),
+      "until_and_including" => Step::UntilAndIncluding(args.get(0)?),
+      "not_starting_with" => Step::NotStartingWith(args.get(0)?),
+      "not_ending_with" => Step::NotEndingWith(args.get(0)?),
+      "line_break" => Step::LineBreak,
+      "br" => Step::Br,
+      "tab" => Step::Tab,
+      "word" => Step::Word,
+      "non_word" => Step::NonWord,
+      "digit" => Step::Digit,
+      "non_digit" => Step::NonDigit,
+      "unicode_digit" => Step::UnicodeDigit,
+      "ascii_digit" => Step::AsciiDigit,
+      "space" => Step::Space,
+      "non_space" => Step::NonSpace,
+      "optional_whitespace" => Step::OptionalWhitespace,
+      "required_whitespace" => Step::RequiredWhitespace,
+      "optional_horizontal_whitespace" => Step::OptionalHorizontalWhitespace,
+      "required_horizontal_whitespace" => Step::RequiredHorizontalWhitespace,
+      "any_of" => Step::AnyOf(args.get(0)?),
+      "any" => Step::Any(args.get(0)?),
+      "range" => Step::Range(args.get(0)?),
+      "add_modifier" => Step::AddModifier(args.get(0)?),
+      "remove_modifier" => Step::RemoveModifier(args.get(0)?),
+      "set_flag" => Step::SetFlag(args.get(0)?),
+      "clear_flag" => Step::ClearFlag(args.get(0)?),
+      "with_any_case" => Step::WithAnyCase,
+      "with_specific_case" => Step::WithSpecificCase,
+      "search_multiline" => Step::SearchMultiline,
+      "search_oneline" => Step::SearchOneline,
+      "multiple" => Step::Multiple(args.get(0)?, args.get(1)?),
+      "repeated" => Step::Repeated(args.get(0)?, args.get(1)?),
+      "one_or_more" => Step::OneOrMore,
+      "zero_or_more" => Step::ZeroOrMore,
+      "count" => Step::Count(args.get(0)?),
+      "count_range" => Step::CountRange(args.get(0)?, args.get(1)?),
+      "count_range_lazy" => Step::CountRangeLazy(args.get(0)?, args.get(1)?),
+      "at_least" => Step::AtLeast(args.get(0)?),
+      "at_least_lazy" => Step::AtLeastLazy(args.get(0)?),
+      "lazy" => Step::Lazy,
+      "possessive" => Step::Possessive,
+      "or" => Step::Or(args.get(0)?),
+      "begin_capture" => Step::BeginCapture,
+      "begin_named_capture" => Step::BeginNamedCapture(args.get(0)?),
+      "begin_capture_tagged" => Step::BeginCaptureTagged(args.get(0)?),
+      "end_capture" => Step::EndCapture,
+      "email" => Step::Email(args.get(0)?),
+      "scheme" => Step::Scheme,
+      "userinfo" => Step::Userinfo,
+      "host" => Step::Host,
+      "port" => Step::Port,
+      "path" => Step::Path,
+      "query" => Step::Query,
+      "fragment" => Step::Fragment,
+      "url" => Step::Url(args.get(0)?),
+      "iso_week_date" => Step::IsoWeekDate,
+      "hashtag" => Step::Hashtag,
+      "mention" => Step::Mention,
+      "word_like" => Step::WordLike(args.get(0)?),
+      "digit_grouped" => Step::DigitGrouped(args.get(0)?, args.get(1)?),
+      "digit_grouped_or_plain" => Step::DigitGroupedOrPlain(args.get(0)?, args.get(1)?),
+      "uuid" => Step::Uuid(args.get(0)?),
+      "line_comment" => Step::LineComment(args.get(0)?),
+      "line_comment_with_newline" => Step::LineCommentWithNewline(args.get(0)?),
+      "block_comment" => Step::BlockComment(args.get(0)?, args.get(1)?),
+      "isbn10" => Step::Isbn10,
+      "isbn13" => Step::Isbn13,
+      "isbn" => Step::Isbn,
+      "ean13" => Step::Ean13,
+      "coordinates" => Step::Coordinates(args.get(0)?),
+      "password_policy" => Step::PasswordPolicy(args.get(0)?),
+      "percent" => Step::Percent,
+      "ratio" => Step::Ratio,
+      "numeric_range" => Step::NumericRange(args.get(0)?),
+      "digit_n" => Step::DigitN(args.get(0)?),
+      "non_digit_n" => Step::NonDigitN(args.get(0)?),
+      "space_n" => Step::SpaceN(args.get(0)?),
+      "non_space_n" => Step::NonSpaceN(args.get(0)?),
+      "tab_n" => Step::TabN(args.get(0)?),
+      "wordlist" => Step::Wordlist(args.get(0)?, args.get(1)?),
+      "keyword_of" => Step::KeywordOf(args.get(0)?),
+      "css_color" => Step::CssColor,
+      "html_entity" => Step::HtmlEntity,
+      "percent_encoded" => Step::PercentEncoded,
+      "percent_encoded_run" => Step::PercentEncodedRun,
+      "quoted_string" => Step::QuotedString,
+      "json_string" => Step::JsonString,
+      "json_number" => Step::JsonNumber,
+      "roman_numeral" => Step::RomanNumeral(args.get(0)?),
+      "ordinal" => Step::Ordinal,
+      "license_plate" => Step::LicensePlate(args.get(0)?),
+      "anything_up_to" => Step::AnythingUpTo(args.get(0)?),
+      "something_up_to" => Step::SomethingUpTo(args.get(0)?),
+      "unbounded" => Step::Unbounded(args.get(0)?),
+      "doi" => Step::Doi,
+      "arxiv_id" => Step::ArxivId,
+      "citation" => Step::Citation,
+      "byte_range" => Step::ByteRange(args.get(0)?, args.get(1)?),
+      "ascii_printable" => Step::AsciiPrintable,
+      "non_ascii_byte" => Step::NonAsciiByte,
+      "bytes_of" => Step::BytesOf(args.get(0)?),
+      "windows1252_bytes" => Step::Windows1252Bytes,
+      "identifier" => Step::Identifier(args.get(0)?),
+      "xml_name" => Step::XmlName,
+      "column" => Step::Column(args.get(0)?, args.get(1)?),
+      "columns" => {
+        let (first, last) = args.range(0)?;
+        Step::Columns(first, last, args.get(1)?)
+      },
+      "commit_sha" => Step::CommitSha(args.get(0)?),
+      "git_ref" => Step::GitRef,
+      "branch_name" => Step::BranchName,
+      "hostname" => Step::Hostname(args.get(0)?),
+      "domain_name" => Step::DomainName(args.get(0)?),
+      "indented" => Step::Indented(args.get(0)?, args.get(1)?),
+      "indentation_capture" => Step::IndentationCapture,
+      "dedent_guard" => Step::DedentGuard,
+      "decimal_with" => Step::DecimalWith(args.get(0)?),
+      "date_with" => Step::DateWith(args.get(0)?),
+      "duration_component" => Step::DurationComponent,
+      "duration" => Step::Duration,
+      "timezone_offset" => Step::TimezoneOffset,
+      "whitespace_trimmed" => Step::WhitespaceTrimmed,
+      "times" => Step::Times(args.get(0)?),
+      "with_total_length" => Step::WithTotalLength(args.get(0)?, args.get(1)?),
+      "insert" => Step::Insert(args.get(0)?),
+      "auto_close_groups" => Step::AutoCloseGroups(args.get(0)?),
+      "tolerant_whitespace" => Step::TolerantWhitespace(args.get(0)?),
+      "duplicate_groups" => Step::DuplicateGroups(args.get(0)?),
+      #[cfg(feature = "unicode-normalization")]
+      "normalize_literals" => Step::NormalizeLiterals(args.get(0)?),
+      "ascii_only" => Step::AsciiOnly,
+    _ => {
+      let message = format!("`{}` is not a builder method supported by verex_static!", method);
+      return Err(Error::new(call.method.span(), message));
+    },
+  };
+  args.finish(&method)?;
+  Ok(step)
+}
+
+/// The arguments of a call or variant, read in order by type.
+struct Args<'a> {
+  span: proc_macro2::Span,
+  exprs: &'a [Expr],
+  read: Cell<usize>,
+}
+
+impl<'a> Args<'a> {
+  fn new(span: proc_macro2::Span, exprs: &'a [Expr]) -> Args<'a> {
+    Args { span, exprs, read: Cell::new(0) }
+  }
+
+  fn expr(&self, i: usize) -> Result<&'a Expr, Error> {
+    self.read.set(self.read.get().max(i + 1));
+    self.exprs.get(i).ok_or_else(|| Error::new(self.span, format!("expected an argument at position {}", i + 1)))
+  }
+
+  fn get<T: FromArg>(&self, i: usize) -> Result<T, Error> {
+    T::from_arg(self.expr(i)?)
+  }
+
+  /// The argument after the last one read.
+  fn next<T: FromArg>(&self) -> Result<T, Error> {
+    self.get(self.read.get())
+  }
+
+  /// The bounds of an inclusive range such as `2..=4`.
+  fn range<T: FromArg>(&self, i: usize) -> Result<(T, T), Error> {
+    match *self.expr(i)? {
+      Expr::Range(ref range) => match (&range.start, &range.limits, &range.end) {
+        (Some(start), &RangeLimits::Closed(_), Some(end)) => Ok((T::from_arg(start)?, T::from_arg(end)?)),
+        _ => Err(Error::new(range.span(), "expected an inclusive range such as `2..=4`")),
+      },
+      ref expr => Err(Error::new(expr.span(), "expected an inclusive range such as `2..=4`")),
+    }
+  }
+
+  /// Checks that every argument was read.
+  fn finish(&self, name: &str) -> Result<(), Error> {
+    match self.exprs.get(self.read.get()) {
+      Some(extra) => Err(Error::new(extra.span(), format!("`{}` takes {} arguments", name, self.read.get()))),
+      None => Ok(()),
+    }
+  }
+}
+
+/// A value that can be read from a literal argument.
+trait FromArg: Sized {
+  fn from_arg(expr: &Expr) -> Result<Self, Error>;
+
+  /// The values of a byte string literal, for types that can be read from one.
+  fn from_bytes(_: &[u8]) -> Option<Vec<Self>> {
+    None
+  }
+}
+
+/// The expression inside any references, parentheses and invisible groups.
+fn unwrap(mut expr: &Expr) -> &Expr {
+  loop {
+    expr = match *expr {
+      Expr::Reference(ref reference) => &reference.expr,
+      Expr::Paren(ref paren) => &paren.expr,
+      Expr::Group(ref group) => &group.expr,
+      _ => return expr,
+    };
+  }
+}
+
+fn expected(expr: &Expr, what: &str) -> Error {
+  Error::new(expr.span(), format!("expected {}", what))
+}
+
+impl FromArg for String {
+  fn from_arg(expr: &Expr) -> Result<String, Error> {
+    match *unwrap(expr) {
+      Expr::Lit(ref lit) => match lit.lit {
+        Lit::Str(ref s) => Ok(s.value()),
+        Lit::Char(ref c) => Ok(c.value().to_string()),
+        _ => Err(expected(expr, "a string literal")),
+      },
+      _ => Err(expected(expr, "a string literal")),
+    }
+  }
+}
+
+impl FromArg for char {
+  fn from_arg(expr: &Expr) -> Result<char, Error> {
+    match *unwrap(expr) {
+      Expr::Lit(ref lit) => match lit.lit {
+        Lit::Char(ref c) => Ok(c.value()),
+        _ => Err(expected(expr, "a character literal")),
+      },
+      _ => Err(expected(expr, "a character literal")),
+    }
+  }
+}
+
+impl FromArg for bool {
+  fn from_arg(expr: &Expr) -> Result<bool, Error> {
+    match *unwrap(expr) {
+      Expr::Lit(ref lit) => match lit.lit {
+        Lit::Bool(ref b) => Ok(b.value),
+        _ => Err(expected(expr, "`true` or `false`")),
+      },
+      _ => Err(expected(expr, "`true` or `false`")),
+    }
+  }
+}
+
+/// Reads an integer literal, negated or not, as any integer type.
+fn integer<T: FromStr>(expr: &Expr) -> Result<T, Error> where T::Err: std::fmt::Display {
+  match *unwrap(expr) {
+    Expr::Lit(ref lit) => match lit.lit {
+      Lit::Int(ref i) => i.base10_parse(),
+      _ => Err(expected(expr, "an integer literal")),
+    },
+    Expr::Unary(ref unary) => match (&unary.op, unwrap(&unary.expr)) {
+      (&UnOp::Neg(_), Expr::Lit(lit)) => match lit.lit {
+        Lit::Int(ref i) => format!("-{}", i.base10_digits()).parse().map_err(|err| Error::new(expr.span(), err)),
+        _ => Err(expected(expr, "an integer literal")),
+      },
+      _ => Err(expected(expr, "an integer literal")),
+    },
+    _ => Err(expected(expr, "an integer literal")),
+  }
+}
+
+impl FromArg for u8 {
+  fn from_arg(expr: &Expr) -> Result<u8, Error> {
+    match *unwrap(expr) {
+      Expr::Lit(ref lit) => match lit.lit {
+        Lit::Byte(ref b) => Ok(b.value()),
+        _ => integer(expr),
+      },
+      _ => integer(expr),
+    }
+  }
+
+  fn from_bytes(bytes: &[u8]) -> Option<Vec<u8>> {
+    Some(bytes.to_vec())
+  }
+}
+
+impl FromArg for i32 {
+  fn from_arg(expr: &Expr) -> Result<i32, Error> {
+    integer(expr)
+  }
+}
+
+impl FromArg for u32 {
+  fn from_arg(expr: &Expr) -> Result<u32, Error> {
+    integer(expr)
+  }
+}
+
+impl FromArg for usize {
+  fn from_arg(expr: &Expr) -> Result<usize, Error> {
+    integer(expr)
+  }
+}
+
+/// `None`, `Some(value)`, or a bare value, as `Into<Option<T>>` arguments take.
+impl<T: FromArg> FromArg for Option<T> {
+  fn from_arg(expr: &Expr) -> Result<Option<T>, Error> {
+    match variant(expr) {
+      Ok((ref name, ref args)) if name == "None" && args.is_empty() => Ok(None),
+      Ok((ref name, ref args)) if name == "Some" && args.len() == 1 => T::from_arg(&args[0]).map(Some),
+      _ => T::from_arg(expr).map(Some),
+    }
+  }
+}
+
+impl<T: FromArg> FromArg for Vec<T> {
+  fn from_arg(expr: &Expr) -> Result<Vec<T>, Error> {
+    match *unwrap(expr) {
+      Expr::Array(ref array) => array.elems.iter().map(T::from_arg).collect(),
+      Expr::Lit(ref lit) => match lit.lit {
+        Lit::ByteStr(ref bytes) => T::from_bytes(&bytes.value()).ok_or_else(|| expected(expr, "an array")),
+        _ => Err(expected(expr, "an array")),
+      },
+      _ => Err(expected(expr, "an array")),
+    }
+  }
+}
+
+impl<A: FromArg, B: FromArg> FromArg for (A, B) {
+  fn from_arg(expr: &Expr) -> Result<(A, B), Error> {
+    match *unwrap(expr) {
+      Expr::Tuple(ref tuple) if tuple.elems.len() == 2 => Ok((A::from_arg(&tuple.elems[0])?, B::from_arg(&tuple.elems[1])?)),
+      _ => Err(expected(expr, "a pair")),
+    }
+  }
+}
+
+/// The name of the variant that a path such as `Rep::OneOrMore` or a call such as
+/// `Rep::Exactly(2)` gives, and the arguments of the call.
+fn variant(expr: &Expr) -> Result<(String, Vec<Expr>), Error> {
+  let last = |path: &syn::Path| path.segments.last().map(|segment| segment.ident.to_string());
+  match *unwrap(expr) {
+    Expr::Path(ref path) => last(&path.path).map(|name| (name, Vec::new())).ok_or_else(|| expected(expr, "a variant")),
+    Expr::Call(ref call) => match *call.func {
+      Expr::Path(ref path) => last(&path.path).map(|name| (name, call.args.iter().cloned().collect())).ok_or_else(|| expected(expr, "a variant")),
+      _ => Err(expected(expr, "a variant")),
+    },
+    _ => Err(expected(expr, "a variant")),
+  }
+}
+
+/// Implements `FromArg` for an enum, from its variants and the fields of each.
+macro_rules! from_variant {
+  ($ty:ident { $($variant:ident $(($($field:ident),*))*),* $(,)* }) => {
+    impl FromArg for $ty {
+      fn from_arg(expr: &Expr) -> Result<$ty, Error> {
+        let (name, exprs) = variant(expr)?;
+        let args = Args::new(expr.span(), &exprs);
+        let value = match &name[..] {
+          $(stringify!($variant) => $ty::$variant $(($({ let $field = args.next()?; $field }),*))*,)*
+          _ => return Err(expected(expr, concat!("a variant of `", stringify!($ty), "`"))),
+        };
+        args.finish(&name)?;
+        Ok(value)
+      }
+    }
+  };
+}
+
+from_variant!(Rep { Optional, ZeroOrMore, OneOrMore, Exactly(n), AtLeast(n), Between(min, max) });
+from_variant!(Flag { CaseInsensitive, MultiLine, DotMatchesNewline, SwapGreed, Unicode, IgnoreWhitespace, CRLF });
+from_variant!(Unbounded { Allow, Forbid, Cap(n) });
+from_variant!(DuplicateGroups { Reject, Suffix });
+from_variant!(EmailStrictness { Simple, Practical, Strict });
+from_variant!(UrlPart { Required, Optional, Absent });
+from_variant!(CoordSeparator { Comma, Whitespace, CommaOrWhitespace });
+from_variant!(RomanCase { Upper, Lower, Any });
+from_variant!(PlateRegion { UsGeneric, Uk, De, Jp });
+from_variant!(IndentUnit { Spaces(n), Tab });
+from_variant!(IdentLang { Rust, C, Js, XmlName });
+from_variant!(DateOrder { DMY, MDY, YMD });
+
+/// The fields of a struct expression such as `UuidOpts { version: Some(4), ..Default::default() }`,
+/// and whether it ends in a `..` base.
+fn fields(expr: &Expr) -> Result<(Vec<(String, Expr)>, bool), Error> {
+  match *unwrap(expr) {
+    Expr::Struct(ref strukt) => {
+      let mut fields = Vec::new();
+      for field in &strukt.fields {
+        match field.member {
+          syn::Member::Named(ref ident) => fields.push((ident.to_string(), field.expr.clone())),
+          syn::Member::Unnamed(_) => return Err(expected(expr, "named fields")),
+        }
+      }
+      Ok((fields, strukt.rest.is_some()))
+    },
+    _ => Err(expected(expr, "a struct expression")),
+  }
+}
+
+/// Implements `FromArg` for a struct of options from its fields. Those with `Default` take a
+/// `..Default::default()` base for the fields left out.
+macro_rules! from_fields {
+  ($ty:ident: Default { $($field:ident),* }) => {
+    impl FromArg for $ty {
+      fn from_arg(expr: &Expr) -> Result<$ty, Error> {
+        let (fields, _) = fields(expr)?;
+        let mut value = $ty::default();
+        for (name, field) in &fields {
+          match &name[..] {
+            $(stringify!($field) => value.$field = FromArg::from_arg(field)?,)*
+            _ => return Err(Error::new(field.span(), format!("`{}` has no field `{}`", stringify!($ty), name))),
+          }
+        }
+        Ok(value)
+      }
+    }
+  };
+  ($ty:ident { $($field:ident),* }) => {
+    impl FromArg for $ty {
+      fn from_arg(expr: &Expr) -> Result<$ty, Error> {
+        let (fields, _) = fields(expr)?;
+        let field = |wanted: &str| {
+          fields.iter().find(|&(name, _)| name == wanted).map(|(_, field)| field)
+            .ok_or_else(|| Error::new(expr.span(), format!("missing field `{}` of `{}`", wanted, stringify!($ty))))
+        };
+        Ok($ty { $($field: FromArg::from_arg(field(stringify!($field))?)?),* })
+      }
+    }
+  };
+}
+
+from_fields!(UuidOpts: Default { version, rfc4122_variant, any_case, braced, urn });
+from_fields!(UrlOpts: Default { userinfo, port, path, query, fragment });
+from_fields!(CoordOpts: Default { separator, hemisphere });
+from_fields!(HostnameOpts: Default { trailing_dot, idn });
+from_fields!(PasswordPolicy: Default { min_len, max_len, require_upper, require_lower, require_digit, require_special, forbidden_chars });
+from_fields!(WordlistOpts: Default { trie, word_boundaries });
+from_fields!(NumberFormat { thousands, decimal });
+from_fields!(DateFormat { order, sep, four_digit_year });
@@ -0,0 +1,7 @@
+extern crate verbal_expressions;
+
+use verbal_expressions::verex_static;
+
+fn main() {
+  verex_static!(start_of_line().raw("(unclosed").then("x"));
+}
@@ -0,0 +1,10 @@
+extern crate verbal_expressions;
+
+use verbal_expressions::verex_static;
+
+fn main() {
+  verex_static!(then("a").begin_named_capture("9bad").end_capture());
+  verex_static!(capture(|v| v.word()));
+  verex_static!(no_such_method());
+  verex_static!(count("three"));
+}
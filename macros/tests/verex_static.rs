@@ -0,0 +1,32 @@
+extern crate trybuild;
+extern crate verbal_expressions;
+
+use std::ptr;
+use verbal_expressions::{verex_static, CompiledVerex, Rep};
+
+fn version() -> &'static CompiledVerex {
+  verex_static!(start_of_line().then("v").digit_n(Rep::AtLeast(1)).then(".").digit().one_or_more().end_of_line())
+}
+
+#[test]
+fn test_verex_static() {
+  assert!(version().is_match("v1.2"));
+  assert!(!version().is_match("1.2"));
+  assert_eq!(version().as_str(), verbal_expressions::Verex::new().start_of_line().then("v").digit_n(Rep::AtLeast(1)).then(".").digit().one_or_more().end_of_line().as_string());
+}
+
+#[test]
+fn test_verex_static_shared() {
+  assert!(ptr::eq(version(), version()));
+  assert!(version().is_match("v10.0"));
+
+  let pair = verex_static!(begin_named_capture("key").word().end_capture().then("=").digit_n(Rep::Between(1, 3)));
+  assert_eq!(pair.find_first_named("x=1 size=10").unwrap()["key"], "x");
+  let second = verex_static!(search_multiline().column(2, ','));
+  assert_eq!(second.captures("a,b,c"), vec!["a,b", "b"]);
+}
+
+#[test]
+fn test_verex_static_errors() {
+  trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
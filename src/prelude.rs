@@ -0,0 +1,32 @@
+//! Glob-import this module to bring the builder and its companion types and traits into scope.
+
+pub use alternation::Alternation;
+pub use builder::{CompositionMap, DuplicateGroups, Flag, Rep, Step, Unbounded, Verex, VerexFragment};
+pub use classes::RangeBound;
+pub use compiled::{CaptureTuple, CaptureValue, CompiledVerex, Flags, ReplacementTemplate, SharedVerex, VerexStats};
+pub use error::VerexError;
+pub use escape::{escape, escape_class};
+pub use extract::{CompiledExtractor, Extractor};
+pub use iter::{GroupRanges, Highlight, Segment, SplitPart, StreamMatcher};
+pub use keyword::KeywordMatcher;
+#[cfg(feature = "unicode-normalization")]
+pub use normalize::NfcText;
+pub use presets::{CoordOpts, CoordSeparator, DateFormat, DateOrder, EmailStrictness, HostnameOpts, IdentLang, IndentUnit, NumberFormat, PasswordPolicy, PlateRegion, RomanCase, UrlOpts, UrlPart, UuidOpts, WordlistOpts};
+pub use scanner::{annotate, Annotation, VerexScanner};
+pub use strict::VerexBuilder;
+pub use syntax::{Flavor, UnsupportedConstruct};
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_prelude() {
+    let v: Verex = Verex::new().start_of_line().range(&[('a', 'z')]).one_or_more().end_of_line();
+    let compiled: CompiledVerex = v.compile().unwrap();
+    assert!(compiled.is_match("abc"));
+    let (word,): (String,) = Verex::new().begin_capture().word().end_capture().compile().unwrap().captures_tuple("hi").unwrap().unwrap();
+    assert_eq!(word, "hi");
+    assert!(Verex::new().email(EmailStrictness::Simple).url(UrlOpts::default()).try_as_regex().is_ok());
+  }
+}